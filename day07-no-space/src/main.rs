@@ -1,21 +1,38 @@
+use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 
-fn parse_line(line: &str) -> Line {
-    let mut words = line.split(" ");
-    let first = words.next().unwrap();
-    match first {
-        "$" => match words.next().unwrap() {
-            "ls" => Line::ListFiles,
-            "cd" => Line::ChangeDirectory(words.next().unwrap().into()),
-            _ => unreachable!(),
+mod planner;
+mod query;
+
+fn parse_line(line: &str) -> Result<Line, String> {
+    let mut words = line.split(' ');
+    let mut word = || {
+        words
+            .next()
+            .ok_or_else(|| format!("incomplete line {line:?}"))
+    };
+    match word()? {
+        "$" => match word()? {
+            "ls" => Ok(Line::ListFiles),
+            "cd" => Ok(Line::ChangeDirectory(word()?.into())),
+            command => Err(format!("unknown command {command}")),
         },
-        "dir" => Line::Directory(words.next().unwrap().into()),
-        size => Line::File(words.next().unwrap().into(), size.parse().unwrap()),
+        "dir" => Ok(Line::Directory(word()?.into())),
+        size => {
+            let size = size
+                .parse()
+                .map_err(|_| format!("invalid file size {size}"))?;
+            Ok(Line::File(word()?.into(), size))
+        }
     }
 }
 
-fn parse(input: &str) -> Vec<Line> {
-    input.lines().map(parse_line).collect()
+fn parse(input: &str) -> Result<Vec<Line>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| parse_line(line).map_err(|e| format!("line {}: {e}", index + 1)))
+        .collect()
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -50,55 +67,102 @@ pub struct Solver {
     root: Dir,
 }
 
-fn add_file(dir: &mut Dir, path: &[String], name: &str, size: u64) -> u64 {
+fn add_file(dir: &mut Dir, path: &[String], name: &str, size: u64) -> Result<u64, String> {
     if let Some(next) = path.first() {
         let next = match dir.entries.get_mut(next) {
             Some(Node::Dir(dir)) => dir,
-            _ => unreachable!(),
+            _ => return Err(format!("{next} is not a directory")),
         };
-        let add = add_file(next, &path[1..path.len()], name, size);
+        let add = add_file(next, &path[1..], name, size)?;
         dir.total += add;
-        add
+        Ok(add)
     } else {
-        if let Some(Node::File(_existing)) = dir.entries.get(name) {
-            0
-        } else {
-            dir.entries.insert(name.to_string(), Node::File(size));
-            dir.total += size;
-            size
+        match dir.entries.get(name) {
+            Some(Node::File(existing)) if *existing == size => Ok(0),
+            Some(Node::File(existing)) => Err(format!(
+                "{name} was listed with size {existing} before, now {size}"
+            )),
+            Some(Node::Dir(_)) => Err(format!("{name} is both a directory and a file")),
+            None => {
+                dir.entries.insert(name.to_string(), Node::File(size));
+                dir.total += size;
+                Ok(size)
+            }
         }
     }
 }
 
 impl Solver {
-    fn parse(&mut self, line: &Line) {
-        match line {
-            Line::ChangeDirectory(path) => match path.as_str() {
+    // absolute or relative to the current directory, without checking that
+    // the directories exist.
+    fn resolve(&self, path: &str) -> Result<Vec<String>, String> {
+        let mut resolved = if path.starts_with('/') {
+            vec![]
+        } else {
+            self.position.clone()
+        };
+        for part in path.split('/') {
+            match part {
+                "" | "." => {}
                 ".." => {
-                    self.position.pop();
-                }
-                "/" => self.position.clear(),
-                dir => {
-                    // make sure dir exists
-                    self.parse(&Line::Directory(path.to_string()));
-                    self.position.push(dir.to_string());
+                    resolved
+                        .pop()
+                        .ok_or_else(|| format!("{path} leads above the root"))?;
                 }
-            },
+                name => resolved.push(name.to_string()),
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn dir(&self, path: &[String]) -> Result<&Dir, String> {
+        let mut current = &self.root;
+        for (index, name) in path.iter().enumerate() {
+            let location = format!("/{}", path[..=index].join("/"));
+            current = match current.entries.get(name) {
+                Some(Node::Dir(dir)) => dir,
+                Some(Node::File(_)) => return Err(format!("{location} is not a directory")),
+                None => return Err(format!("{location} does not exist")),
+            };
+        }
+        Ok(current)
+    }
+
+    fn dir_mut(&mut self) -> &mut Dir {
+        let mut current = &mut self.root;
+        for name in &self.position {
+            current = match current.entries.get_mut(name) {
+                Some(Node::Dir(dir)) => dir,
+                // the position is only ever set to existing directories
+                _ => unreachable!(),
+            };
+        }
+        current
+    }
+
+    fn parse(&mut self, line: &Line) -> Result<(), String> {
+        match line {
+            Line::ChangeDirectory(path) => {
+                let path = self.resolve(path)?;
+                self.dir(&path)?;
+                self.position = path;
+            }
             Line::ListFiles => {}
-            Line::Directory(dir) => {
-                let mut current = &mut self.root;
-                for path in &self.position {
-                    current = match current.entries.get_mut(path) {
-                        Some(Node::Dir(dir)) => dir,
-                        _ => unreachable!(),
-                    };
+            Line::Directory(name) => match self.dir_mut().entries.entry(name.clone()) {
+                Entry::Occupied(entry) => {
+                    if let Node::File(_) = entry.get() {
+                        return Err(format!("{name} is both a file and a directory"));
+                    }
                 }
-                current.entries.entry(dir.clone()).or_default();
-            }
+                Entry::Vacant(entry) => {
+                    entry.insert(Node::default());
+                }
+            },
             Line::File(name, size) => {
-                add_file(&mut self.root, &self.position[..], name, *size);
+                add_file(&mut self.root, &self.position, name, *size)?;
             }
         }
+        Ok(())
     }
 
     fn solve_dir(&self, max: u64, dir: &Dir) -> u64 {
@@ -122,34 +186,151 @@ impl Solver {
 
     fn solve_two_dir(&self, needs: u64, dir: &Dir) -> Option<u64> {
         dir.entries
-            .iter()
-            .filter_map(|(_key, entry)| match entry {
+            .values()
+            .filter_map(|entry| match entry {
                 Node::Dir(dir) => Some(dir),
                 _ => None,
             })
             .filter_map(|dir| self.solve_two_dir(needs, dir))
-            .chain(dir.total.checked_sub(needs).map(|_| dir.total).into_iter())
+            .chain(dir.total.checked_sub(needs).map(|_| dir.total))
             .min()
     }
 
-    fn solve_two(&self, needs: u64, has: u64) -> u64 {
-        let free = has - self.root.total;
-        let needs = needs - free;
-        self.solve_two_dir(needs, &self.root).unwrap()
+    // how much has to be deleted to get the needed free space
+    fn to_free(&self, config: &Config) -> Result<u64, String> {
+        let used = self.root.total;
+        let free = config.disk.checked_sub(used).ok_or_else(|| {
+            format!(
+                "{used} bytes are used, more than the disk size of {}",
+                config.disk
+            )
+        })?;
+        Ok(config.needed.saturating_sub(free))
+    }
+
+    // size of the smallest directory that frees enough space, or zero when
+    // there already is enough.
+    fn solve_two(&self, config: &Config) -> Result<u64, String> {
+        match self.to_free(config)? {
+            0 => Ok(0),
+            needs => self
+                .solve_two_dir(needs, &self.root)
+                .ok_or_else(|| format!("no directory frees the {needs} bytes needed")),
+        }
+    }
+
+    fn explain(&self, config: &Config) -> Result<String, String> {
+        let needs = self.to_free(config)?;
+        let mut report = format!(
+            "disk size {:>12}\nused      {:>12}\nfree      {:>12}\nneeded    {:>12}\nto free   {needs:>12}\n",
+            config.disk,
+            self.root.total,
+            config.disk - self.root.total,
+            config.needed,
+        );
+        if needs == 0 {
+            report.push_str("\nthere is enough free space already, nothing needs to be deleted\n");
+            return Ok(report);
+        }
+        let (large, small): (Vec<_>, Vec<_>) = query::largest_dirs(&self.root, usize::MAX)
+            .into_iter()
+            .partition(|(_, size)| *size >= needs);
+        report.push_str("\nlarge enough:\n");
+        for (index, (path, size)) in large.iter().rev().enumerate() {
+            let reason = if index == 0 {
+                "  <- smallest directory that frees enough"
+            } else {
+                ""
+            };
+            report.push_str(&format!("{size:>12}  {path}{reason}\n"));
+        }
+        report.push_str("\ntoo small:\n");
+        for (path, size) in small {
+            report.push_str(&format!("{size:>12}  {path}  ({} short)\n", needs - size));
+        }
+        Ok(report)
     }
 }
 
-fn solve(lines: &[Line]) -> (u64, u64) {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    // directories smaller than this count towards the first answer
+    max_size: u64,
+    disk: u64,
+    needed: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_size: 100000,
+            disk: 70000000,
+            needed: 30000000,
+        }
+    }
+}
+
+impl Config {
+    // takes `--max-size`, `--disk` and `--needed` out of the arguments
+    fn from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut config = Config::default();
+        while let Some(index) = args.iter().position(|arg| arg.starts_with("--")) {
+            let flag = args.remove(index);
+            if index >= args.len() {
+                return Err(format!("{flag} needs a value"));
+            }
+            let value = args.remove(index);
+            let value = value
+                .parse()
+                .map_err(|_| format!("invalid value {value} for {flag}"))?;
+            match flag.as_str() {
+                "--max-size" => config.max_size = value,
+                "--disk" => config.disk = value,
+                "--needed" => config.needed = value,
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.disk == 0 {
+            return Err("the disk size must not be zero".into());
+        }
+        if self.needed > self.disk {
+            return Err(format!(
+                "{} bytes can never be free on a disk of {} bytes",
+                self.needed, self.disk
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn build(lines: &[Line]) -> Result<Solver, String> {
     let mut solver = Solver::default();
-    for line in lines {
-        solver.parse(&line);
+    for (index, line) in lines.iter().enumerate() {
+        solver
+            .parse(line)
+            .map_err(|e| format!("line {}: {e}", index + 1))?;
     }
-    (solver.solve(100000), solver.solve_two(30000000, 70000000))
+    Ok(solver)
+}
+
+fn solve(solver: &Solver, config: &Config) -> Result<(u64, u64), String> {
+    Ok((solver.solve(config.max_size), solver.solve_two(config)?))
+}
+
+#[cfg(test)]
+fn example() -> Dir {
+    let lines = parse(include_str!("../example.txt")).unwrap();
+    build(&lines).unwrap().root
 }
 
 #[test]
 fn can_parse() {
-    let lines = parse(include_str!("../example.txt"));
+    let lines = parse(include_str!("../example.txt")).unwrap();
     assert_eq!(lines[0], Line::ChangeDirectory("/".into()));
     assert_eq!(lines[1], Line::ListFiles);
     assert_eq!(lines[2], Line::Directory("a".into()));
@@ -160,17 +341,214 @@ fn can_parse() {
 
 #[test]
 fn can_solve() {
-    let lines = parse(include_str!("../example.txt"));
-    let (one, two) = solve(&lines);
+    let lines = parse(include_str!("../example.txt")).unwrap();
+    let (one, two) = solve(&build(&lines).unwrap(), &Config::default()).unwrap();
     assert_eq!(95437, one);
     assert_eq!(24933642, two);
 }
 
+#[test]
+fn can_resolve_paths() {
+    let lines = parse(include_str!("../example.txt")).unwrap();
+    let mut solver = build(&lines[..13]).unwrap();
+    assert_eq!(solver.position, vec!["a", "e"]);
+    assert_eq!(solver.resolve("..").unwrap(), vec!["a"]);
+    assert_eq!(solver.resolve("../../d").unwrap(), vec!["d"]);
+    assert_eq!(solver.resolve("/a/./e/").unwrap(), vec!["a", "e"]);
+    assert_eq!(solver.resolve("/").unwrap(), Vec::<String>::new());
+    assert!(solver.resolve("../../..").is_err());
+    solver.parse(&Line::ChangeDirectory("/a".into())).unwrap();
+    assert_eq!(solver.position, vec!["a"]);
+    solver
+        .parse(&Line::ChangeDirectory("e/../../d".into()))
+        .unwrap();
+    assert_eq!(solver.position, vec!["d"]);
+}
+
+#[test]
+fn can_report_errors() {
+    let error = |input: &str| build(&parse(input).unwrap()).unwrap_err();
+    assert_eq!(error("$ cd /\n$ cd .."), "line 2: .. leads above the root");
+    assert_eq!(error("$ cd x"), "line 1: /x does not exist");
+    assert_eq!(error("$ ls\n10 a\n$ cd a"), "line 3: /a is not a directory");
+    assert_eq!(
+        error("$ ls\ndir a\n$ cd a\n$ ls\n5 b\n7 b"),
+        "line 6: b was listed with size 5 before, now 7"
+    );
+    assert_eq!(
+        error("$ ls\ndir a\n10 a"),
+        "line 3: a is both a directory and a file"
+    );
+    assert_eq!(
+        error("$ ls\n10 a\ndir a"),
+        "line 3: a is both a file and a directory"
+    );
+    assert_eq!(parse("$ rm a"), Err("line 1: unknown command rm".into()));
+    assert_eq!(
+        parse("$ ls\nabc d"),
+        Err("line 2: invalid file size abc".into())
+    );
+    assert_eq!(parse("dir"), Err("line 1: incomplete line \"dir\"".into()));
+
+    // listing the same file twice does not count it twice
+    let lines = parse("$ ls\n10 a\n$ ls\n10 a").unwrap();
+    assert_eq!(build(&lines).unwrap().root.total, 10);
+}
+
+#[test]
+fn can_configure() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let mut rest = args(&["--disk", "100", "du", "--needed", "40", "2"]);
+    let config = Config::from_args(&mut rest).unwrap();
+    assert_eq!(rest, args(&["du", "2"]));
+    assert_eq!(
+        config,
+        Config {
+            max_size: 100000,
+            disk: 100,
+            needed: 40
+        }
+    );
+    assert!(Config::from_args(&mut args(&["--disk", "0"])).is_err());
+    assert!(Config::from_args(&mut args(&["--disk", "10", "--needed", "11"])).is_err());
+    assert!(Config::from_args(&mut args(&["--disk", "-1"])).is_err());
+    assert!(Config::from_args(&mut args(&["--needed"])).is_err());
+    assert!(Config::from_args(&mut args(&["--size", "1"])).is_err());
+}
+
+#[test]
+fn can_solve_with_config() {
+    let lines = parse(include_str!("../example.txt")).unwrap();
+    let solver = build(&lines).unwrap();
+    let config = |max_size, disk, needed| Config {
+        max_size,
+        disk,
+        needed,
+    };
+    // 48381165 bytes are used
+    assert_eq!(
+        solve(&solver, &config(1000, 70000000, 30000000)),
+        Ok((584, 24933642))
+    );
+    assert_eq!(solver.solve_two(&config(0, 48381165 + 100, 101)), Ok(584));
+    assert_eq!(solver.solve_two(&config(0, 80000000, 30000000)), Ok(0));
+    assert_eq!(
+        solver.solve_two(&config(0, 48381165, 48381165)),
+        Ok(48381165)
+    );
+    assert!(solver.solve_two(&config(0, 40000000, 0)).is_err());
+}
+
+#[test]
+fn can_explain() {
+    let lines = parse(include_str!("../example.txt")).unwrap();
+    let solver = build(&lines).unwrap();
+    let report = solver.explain(&Config::default()).unwrap();
+    assert!(report.contains("to free        8381165\n"));
+    assert!(report.contains("    24933642  /d  <- smallest directory that frees enough\n"));
+    assert!(report.contains("    48381165  /\n"));
+    assert!(report.contains("       94853  /a  (8286312 short)\n"));
+    let config = Config {
+        disk: 100000000,
+        ..Config::default()
+    };
+    assert!(solver
+        .explain(&config)
+        .unwrap()
+        .contains("nothing needs to be deleted"));
+}
+
+fn run(mut args: Vec<String>) -> Result<(), String> {
+    let file = args
+        .first()
+        .ok_or("usage: day07-no-space <file> [command]")?;
+    let data = std::fs::read_to_string(file).map_err(|e| format!("error reading {file}: {e}"))?;
+    args.remove(0);
+    let config = Config::from_args(&mut args)?;
+    let solver = build(&parse(&data)?)?;
+    let root = &solver.root;
+    let argument = |index: usize, name: &str| {
+        args.get(index)
+            .map(|arg| arg.as_str())
+            .ok_or_else(|| format!("{} needs {name}", args[0]))
+    };
+    let number = |index: usize, default| match args.get(index) {
+        Some(arg) => arg.parse().map_err(|_| format!("invalid number {arg}")),
+        None => Ok(default),
+    };
+    let listing = match args.first().map(|arg| arg.as_str()) {
+        None => {
+            let (result, result_two) = solve(&solver, &config)?;
+            println!("{result}");
+            println!("{result_two}");
+            return Ok(());
+        }
+        Some("plan") => {
+            let files = match args.get(1).map(|arg| arg.as_str()) {
+                None => false,
+                Some("files") => true,
+                Some(other) => return Err(format!("can only plan with files, not {other}")),
+            };
+            let needs = solver.to_free(&config)?;
+            let plan = planner::plan(root, needs, files)
+                .ok_or_else(|| format!("no deletion frees the {needs} bytes needed"))?;
+            println!("{}", plan.freed);
+            if !plan.exact {
+                println!("(approximate, the search space was trimmed)");
+            }
+            for path in plan.deleted {
+                println!("{path}");
+            }
+            return Ok(());
+        }
+        Some("explain") => {
+            print!("{}", solver.explain(&config)?);
+            return Ok(());
+        }
+        Some("tree") => {
+            print!("{}", query::tree(root));
+            return Ok(());
+        }
+        Some("du") => query::du(root, number(1, usize::MAX)?),
+        Some("find") => query::find(root, argument(1, "a pattern")?),
+        Some("top") => match argument(1, "dirs or files")? {
+            "dirs" => query::largest_dirs(root, number(2, 10)?),
+            "files" => query::largest_files(root, number(2, 10)?),
+            other => return Err(format!("can only list the top dirs or files, not {other}")),
+        },
+        Some(other) => return Err(format!("unknown command {other}")),
+    };
+    for (path, size) in listing {
+        println!("{size}\t{path}");
+    }
+    Ok(())
+}
+
+#[test]
+fn can_reject_bad_arguments() {
+    let run = |args: &str| run(args.split(' ').map(String::from).collect());
+    assert_eq!(run("example.txt find"), Err("find needs a pattern".into()));
+    assert_eq!(
+        run("example.txt top"),
+        Err("top needs dirs or files".into())
+    );
+    assert_eq!(
+        run("example.txt top dirs x"),
+        Err("invalid number x".into())
+    );
+    assert_eq!(run("example.txt du -1"), Err("invalid number -1".into()));
+    assert_eq!(run("example.txt plan dirs").map_err(|_| ()), Err(()));
+    assert_eq!(run("example.txt --disk").map_err(|_| ()), Err(()));
+    assert_eq!(
+        run("example.txt frobnicate"),
+        Err("unknown command frobnicate".into())
+    );
+    assert!(run("missing.txt").is_err());
+}
+
 fn main() {
-    let file = std::env::args().nth(1).unwrap();
-    let data = std::fs::read_to_string(file).unwrap();
-    let data = parse(&data);
-    let (result, result_two) = solve(&data);
-    println!("{result}");
-    println!("{result_two}");
+    if let Err(error) = run(std::env::args().skip(1).collect()) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
 }
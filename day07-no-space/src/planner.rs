@@ -0,0 +1,255 @@
+#[cfg(test)]
+use super::example;
+use super::query::join;
+use super::{Dir, Node};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+// above this many partial sums close ones get merged, and the plan is no
+// longer guaranteed to be the best one.
+const MAX_STATES: usize = 2000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plan {
+    pub freed: u64,
+    pub deleted: Vec<String>,
+    pub exact: bool,
+}
+
+// what to delete for a state, shared between all states built on it so
+// that combining two of them does not copy anything.
+#[derive(Debug)]
+enum Deleted {
+    Nothing,
+    Path(String),
+    Both(Rc<Deleted>, Rc<Deleted>, usize),
+}
+
+impl Deleted {
+    fn len(&self) -> usize {
+        match self {
+            Deleted::Nothing => 0,
+            Deleted::Path(_) => 1,
+            Deleted::Both(_, _, len) => *len,
+        }
+    }
+
+    fn both(left: &Rc<Deleted>, right: &Rc<Deleted>) -> Rc<Deleted> {
+        match (left.len(), right.len()) {
+            (0, _) => right.clone(),
+            (_, 0) => left.clone(),
+            (a, b) => Rc::new(Deleted::Both(left.clone(), right.clone(), a + b)),
+        }
+    }
+
+    fn paths(&self, paths: &mut Vec<String>) {
+        match self {
+            Deleted::Nothing => {}
+            Deleted::Path(path) => paths.push(path.clone()),
+            Deleted::Both(left, right, _) => {
+                left.paths(paths);
+                right.paths(paths);
+            }
+        }
+    }
+}
+
+// every amount that can be freed within a directory, with what to delete
+// for it. amounts that reach the target only keep the smallest of them.
+struct States {
+    // amounts below the target, by `amount / step`
+    below: BTreeMap<u64, (u64, Rc<Deleted>)>,
+    best: Option<(u64, Rc<Deleted>)>,
+    step: u64,
+    exact: bool,
+}
+
+impl States {
+    fn empty() -> Self {
+        States {
+            below: BTreeMap::from([(0, (0, Rc::new(Deleted::Nothing)))]),
+            best: None,
+            step: 1,
+            exact: true,
+        }
+    }
+
+    fn offer(&mut self, needs: u64, sum: u64, deleted: Rc<Deleted>) {
+        let better =
+            |(existing, d): &(u64, Rc<Deleted>)| (sum, deleted.len()) < (*existing, d.len());
+        if sum >= needs {
+            if self.best.as_ref().map(better).unwrap_or(true) {
+                self.best = Some((sum, deleted));
+            }
+            return;
+        }
+        let bucket = sum / self.step;
+        match self.below.get(&bucket) {
+            Some(existing) if !better(existing) => {
+                self.exact &= existing.0 == sum;
+            }
+            Some(existing) => {
+                self.exact &= existing.0 == sum;
+                self.below.insert(bucket, (sum, deleted));
+            }
+            None => {
+                self.below.insert(bucket, (sum, deleted));
+            }
+        }
+    }
+
+    // in increasing order, the one reaching the target last
+    fn entries(&self) -> impl Iterator<Item = (u64, &Rc<Deleted>)> {
+        self.below
+            .values()
+            .chain(&self.best)
+            .map(|(sum, deleted)| (*sum, deleted))
+    }
+
+    // every combination of one amount from each side, where past the
+    // target only the smallest combination with each left amount matters.
+    // with too many combinations, close amounts share a bucket.
+    fn combine(self, other: States, needs: u64) -> States {
+        let pairs = (self.below.len() + 1) * (other.below.len() + 1);
+        let mut result = States {
+            below: BTreeMap::new(),
+            best: None,
+            step: match pairs <= MAX_STATES {
+                true => 1,
+                false => needs / MAX_STATES as u64 + 1,
+            },
+            exact: self.exact && other.exact,
+        };
+        for (a, left) in self.entries() {
+            for (b, right) in other.entries() {
+                result.offer(needs, a + b, Deleted::both(left, right));
+                if a + b >= needs {
+                    break;
+                }
+            }
+        }
+        result
+    }
+}
+
+fn states(dir: &Dir, path: &str, needs: u64, files: bool) -> States {
+    let mut result = States::empty();
+    for (name, node) in &dir.entries {
+        let path = join(path, name);
+        let child = match node {
+            Node::Dir(dir) => states(dir, &path, needs, files),
+            Node::File(size) if files => {
+                let mut states = States::empty();
+                states.offer(needs, *size, Rc::new(Deleted::Path(path)));
+                states
+            }
+            Node::File(_) => continue,
+        };
+        result = result.combine(child, needs);
+    }
+    // deleting the directory itself covers everything inside it
+    result.offer(needs, dir.total, Rc::new(Deleted::Path(path.to_string())));
+    result
+}
+
+// deletes as few bytes as possible while freeing at least `needs`, using
+// directories that are not inside each other and, if enabled, single files.
+pub fn plan(root: &Dir, needs: u64, files: bool) -> Option<Plan> {
+    let result = states(root, "/", needs, files);
+    let mut best = result.best;
+    // files add states that can push the right directories out of a merged
+    // bucket, so an approximate plan with files could lose to one without.
+    if files && !result.exact {
+        let key = |(freed, deleted): &(u64, Rc<Deleted>)| (*freed, deleted.len());
+        let dirs = states(root, "/", needs, false).best;
+        if let Some(dirs) = dirs.filter(|d| best.as_ref().map(|b| key(d) < key(b)).unwrap_or(true))
+        {
+            best = Some(dirs);
+        }
+    }
+    let (freed, deleted) = best?;
+    let mut paths = vec![];
+    deleted.paths(&mut paths);
+    Some(Plan {
+        freed,
+        deleted: paths,
+        exact: result.exact,
+    })
+}
+
+#[test]
+fn can_plan_single_directory() {
+    let root = example();
+    let result = plan(&root, 8381165, false).unwrap();
+    assert_eq!(result.freed, 24933642);
+    assert_eq!(result.deleted, vec!["/d"]);
+    assert!(result.exact);
+    assert_eq!(plan(&root, 584, false).unwrap().deleted, vec!["/a/e"]);
+}
+
+#[test]
+fn can_plan_with_files() {
+    let root = example();
+    let result = plan(&root, 8381165, true).unwrap();
+    assert_eq!(result.freed, 8504156);
+    assert_eq!(result.deleted, vec!["/c.dat"]);
+
+    let result = plan(&root, 8100000, true).unwrap();
+    assert_eq!(result.freed, 29116 + 62596 + 8033020);
+    assert_eq!(result.deleted, vec!["/a/f", "/a/h.lst", "/d/d.log"]);
+}
+
+#[test]
+fn can_plan_several_directories() {
+    let lines = "$ cd /\n$ ls\ndir a\ndir b\ndir c\n$ cd a\n$ ls\n60 x\n$ cd ../b\n$ ls\n50 y\n$ cd ../c\n$ ls\n45 z\n";
+    let root = super::build(&super::parse(lines).unwrap()).unwrap().root;
+    // a single directory has to be the whole disk, two of them do better
+    let result = plan(&root, 100, false).unwrap();
+    assert_eq!(result.freed, 105);
+    assert_eq!(result.deleted, vec!["/a", "/c"]);
+    assert_eq!(plan(&root, 200, false), None);
+    assert_eq!(plan(&root, 155, false).unwrap().deleted, vec!["/"]);
+    assert_eq!(plan(&root, 0, false).unwrap().freed, 0);
+}
+
+#[test]
+fn can_merge_close_amounts() {
+    let many = || {
+        let mut states = States::empty();
+        for sum in 1..200 {
+            states.offer(u64::MAX, sum, Rc::new(Deleted::Nothing));
+        }
+        states
+    };
+    // a step of one still keeps every amount
+    let exact = many().combine(many(), 1000);
+    assert!(exact.exact);
+    assert_eq!(exact.below.len(), 399);
+    assert!(exact.best.is_none());
+
+    let merged = many().combine(many(), 100000);
+    assert!(!merged.exact);
+    assert_eq!(merged.step, 51);
+    assert_eq!(merged.below.len(), 8);
+    assert_eq!(merged.below[&1].0, 51);
+}
+
+#[test]
+fn can_plan_files_no_worse_than_directories() {
+    let mut lines = String::from("$ cd /\n$ ls\n");
+    for index in 0..20 {
+        lines += &format!("dir d{index}\n");
+    }
+    for index in 0..20 {
+        let (f, g) = ((index + 1) * 7919, (index + 3) * 104729);
+        lines += &format!("$ cd d{index}\n$ ls\n");
+        lines += &format!("{} f\n{} g\n", f % 1000000 + 1000, g % 1000000 + 1000);
+        lines += "$ cd ..\n";
+    }
+    let root = super::build(&super::parse(&lines).unwrap()).unwrap().root;
+    let dirs = plan(&root, 5942620, false).unwrap();
+    assert_eq!(dirs.freed, 5942620);
+    let files = plan(&root, 5942620, true).unwrap();
+    assert!(!files.exact);
+    assert_eq!(files.freed, 5942620);
+}
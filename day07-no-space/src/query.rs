@@ -0,0 +1,211 @@
+#[cfg(test)]
+use super::example;
+use super::{Dir, Node};
+use std::fmt::Write;
+
+pub fn join(parent: &str, name: &str) -> String {
+    format!("{}/{name}", parent.trim_end_matches('/'))
+}
+
+fn size(node: &Node) -> u64 {
+    match node {
+        Node::Dir(dir) => dir.total,
+        Node::File(size) => *size,
+    }
+}
+
+// every entry below `dir` with its full path, parents before children
+pub fn walk(dir: &Dir) -> Vec<(String, &Node)> {
+    fn visit<'a>(dir: &'a Dir, path: &str, entries: &mut Vec<(String, &'a Node)>) {
+        for (name, node) in &dir.entries {
+            let path = join(path, name);
+            entries.push((path.clone(), node));
+            if let Node::Dir(dir) = node {
+                visit(dir, &path, entries);
+            }
+        }
+    }
+    let mut entries = vec![];
+    visit(dir, "/", &mut entries);
+    entries
+}
+
+// like `tree -s`, with sizes of directories including their contents
+pub fn tree(dir: &Dir) -> String {
+    fn visit(dir: &Dir, prefix: &str, output: &mut String) {
+        let count = dir.entries.len();
+        for (index, (name, node)) in dir.entries.iter().enumerate() {
+            let last = index + 1 == count;
+            let branch = if last { "└── " } else { "├── " };
+            writeln!(output, "{prefix}{branch}[{:>10}]  {name}", size(node)).unwrap();
+            if let Node::Dir(dir) = node {
+                let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+                visit(dir, &prefix, output);
+            }
+        }
+    }
+    let mut output = format!("[{:>10}]  /\n", dir.total);
+    visit(dir, "", &mut output);
+    output
+}
+
+// like `du -d depth`, children before their parents and the root last
+pub fn du(dir: &Dir, depth: usize) -> Vec<(String, u64)> {
+    fn visit(dir: &Dir, path: &str, depth: usize, output: &mut Vec<(String, u64)>) {
+        if depth > 0 {
+            for (name, node) in &dir.entries {
+                if let Node::Dir(child) = node {
+                    visit(child, &join(path, name), depth - 1, output);
+                }
+            }
+        }
+        output.push((path.to_string(), dir.total));
+    }
+    let mut output = vec![];
+    visit(dir, "/", depth, &mut output);
+    output
+}
+
+// shell style patterns with `*` and `?`, matched against the whole name
+pub fn glob(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and the name position it is matched up to
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// files whose name matches the pattern, like `find -name`
+pub fn find(dir: &Dir, pattern: &str) -> Vec<(String, u64)> {
+    walk(dir)
+        .into_iter()
+        .filter_map(|(path, node)| match node {
+            Node::File(size) => Some((path, *size)),
+            Node::Dir(_) => None,
+        })
+        .filter(|(path, _)| glob(pattern, path.rsplit('/').next().unwrap()))
+        .collect()
+}
+
+fn largest(mut entries: Vec<(String, u64)>, count: usize) -> Vec<(String, u64)> {
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(count);
+    entries
+}
+
+pub fn largest_dirs(dir: &Dir, count: usize) -> Vec<(String, u64)> {
+    let dirs = walk(dir).into_iter().filter_map(|(path, node)| match node {
+        Node::Dir(dir) => Some((path, dir.total)),
+        Node::File(_) => None,
+    });
+    largest(
+        std::iter::once(("/".into(), dir.total))
+            .chain(dirs)
+            .collect(),
+        count,
+    )
+}
+
+pub fn largest_files(dir: &Dir, count: usize) -> Vec<(String, u64)> {
+    largest(find(dir, "*"), count)
+}
+
+#[test]
+fn can_print_tree() {
+    let tree = tree(&example());
+    let expected = "\
+[  48381165]  /
+├── [     94853]  a
+│   ├── [       584]  e
+│   │   └── [       584]  i
+│   ├── [     29116]  f
+│   ├── [      2557]  g
+│   └── [     62596]  h.lst
+├── [  14848514]  b.txt
+├── [   8504156]  c.dat
+└── [  24933642]  d
+    ├── [   5626152]  d.ext
+    ├── [   8033020]  d.log
+    ├── [   4060174]  j
+    └── [   7214296]  k
+";
+    assert_eq!(tree, expected);
+}
+
+#[test]
+fn can_compute_du() {
+    let root = example();
+    assert_eq!(du(&root, 0), vec![("/".into(), 48381165)]);
+    assert_eq!(
+        du(&root, 1),
+        vec![
+            ("/a".into(), 94853),
+            ("/d".into(), 24933642),
+            ("/".into(), 48381165)
+        ]
+    );
+    assert_eq!(du(&root, 5)[0], ("/a/e".into(), 584));
+}
+
+#[test]
+fn can_glob() {
+    assert!(glob("*", ""));
+    assert!(glob("*.log", "d.log"));
+    assert!(!glob("*.log", "d.ext"));
+    assert!(glob("d.???", "d.ext"));
+    assert!(!glob("d.??", "d.ext"));
+    assert!(glob("*a*b*", "xxaxxbxx"));
+    assert!(!glob("*a*b", "xxaxxbxx"));
+    assert!(glob("h.lst", "h.lst"));
+}
+
+#[test]
+fn can_find_files() {
+    let root = example();
+    assert_eq!(
+        find(&root, "d.*"),
+        vec![("/d/d.ext".into(), 5626152), ("/d/d.log".into(), 8033020)]
+    );
+    let single: Vec<_> = find(&root, "?").into_iter().map(|(path, _)| path).collect();
+    assert_eq!(single, vec!["/a/e/i", "/a/f", "/a/g", "/d/j", "/d/k"]);
+    assert_eq!(find(&root, "*").len(), 10);
+}
+
+#[test]
+fn can_find_largest() {
+    let root = example();
+    assert_eq!(
+        largest_dirs(&root, 2),
+        vec![("/".into(), 48381165), ("/d".into(), 24933642)]
+    );
+    assert_eq!(largest_dirs(&root, 10).len(), 4);
+    assert_eq!(
+        largest_files(&root, 3),
+        vec![
+            ("/b.txt".into(), 14848514),
+            ("/c.dat".into(), 8504156),
+            ("/d/d.log".into(), 8033020)
+        ]
+    );
+}
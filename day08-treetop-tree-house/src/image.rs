@@ -0,0 +1,187 @@
+use super::views;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+// black through red and yellow to white
+const HEAT: [[u8; 3]; 4] = [[0, 0, 0], [200, 30, 0], [255, 210, 0], [255, 255, 255]];
+const VISIBLE: [u8; 3] = [40, 220, 60];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    fn from_grid<T>(grid: &[Vec<T>], colour: impl Fn(&T) -> [u8; 3]) -> Self {
+        Image {
+            width: grid[0].len(),
+            height: grid.len(),
+            pixels: grid.iter().flatten().map(colour).collect(),
+        }
+    }
+
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels.concat())
+    }
+
+    // uses stored deflate blocks, large forests make large files but there
+    // is no compressor to pull in.
+    pub fn write_png<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+        let mut header = vec![];
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bit truecolour, default compression, filter and no interlace
+        header.extend([8, 2, 0, 0, 0]);
+        chunk(&mut writer, b"IHDR", &header)?;
+        let raw: Vec<u8> = self
+            .pixels
+            .chunks(self.width.max(1))
+            .flat_map(|row| std::iter::once(0).chain(row.concat()))
+            .collect();
+        chunk(&mut writer, b"IDAT", &zlib_stored(&raw))?;
+        chunk(&mut writer, b"IEND", &[])
+    }
+}
+
+fn chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(&[kind.as_slice(), data].concat());
+    writer.write_all(&crc.to_be_bytes())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        output.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        output.push(blocks.peek().is_none() as u8);
+        let length = block.len() as u16;
+        output.extend(length.to_le_bytes());
+        output.extend((!length).to_le_bytes());
+        output.extend(block);
+    }
+    output.extend(adler32(data).to_be_bytes());
+    output
+}
+
+fn heat(fraction: f64) -> [u8; 3] {
+    let scaled = fraction.clamp(0.0, 1.0) * (HEAT.len() - 1) as f64;
+    let index = (scaled as usize).min(HEAT.len() - 2);
+    let t = scaled - index as f64;
+    let (a, b) = (HEAT[index], HEAT[index + 1]);
+    [0, 1, 2].map(|c| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * t).round() as u8)
+}
+
+// heights scaled to the tallest tree, single digits still end up at 9
+fn grey<H: Copy + Into<u32>>(data: &[Vec<H>]) -> impl Fn(&H) -> [u8; 3] {
+    let tallest = data
+        .iter()
+        .flatten()
+        .map(|h| (*h).into())
+        .max()
+        .unwrap_or(0);
+    let tallest = tallest.max(9);
+    move |height| [((*height).into() * 255 / tallest) as u8; 3]
+}
+
+pub fn heights<H: Copy + Into<u32>>(data: &[Vec<H>]) -> Image {
+    Image::from_grid(data, grey(data))
+}
+
+// visible trees in green on top of the dimmed heights
+pub fn visible<H: Copy + Into<u32>>(data: &[Vec<H>], visible: &BTreeSet<(usize, usize)>) -> Image {
+    let grey = grey(data);
+    let mut image = Image::from_grid(data, |height| grey(height).map(|c| c / 2));
+    for (x, y) in visible {
+        image.pixels[y * image.width + x] = VISIBLE;
+    }
+    image
+}
+
+// log scaled, since a few spots score orders of magnitude above the rest
+pub fn scores<H: Copy + Ord>(data: &[Vec<H>]) -> Image {
+    let views = views(data);
+    let max = views.iter().flatten().map(|v| v.score()).max().unwrap_or(0);
+    let scale = ((max + 1) as f64).ln().max(f64::MIN_POSITIVE);
+    Image::from_grid(&views, |view| {
+        heat(((view.score() + 1) as f64).ln() / scale)
+    })
+}
+
+#[test]
+fn can_compute_checksums() {
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    assert_eq!(crc32(b"IEND"), 0xae426082);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+}
+
+#[test]
+fn can_store_zlib_blocks() {
+    assert_eq!(zlib_stored(&[]), [0x78, 1, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]);
+    let data = vec![7; 70000];
+    let stored = zlib_stored(&data);
+    assert_eq!(stored.len(), 2 + 5 + 65535 + 5 + 4465 + 4);
+    assert_eq!(stored[2..7], [0, 0xff, 0xff, 0, 0]);
+    assert_eq!(stored[65542..65547], [1, 0x71, 0x11, 0x8e, 0xee]);
+}
+
+#[test]
+fn can_write_images() {
+    let data = super::parse(include_str!("../example.txt")).unwrap();
+    let image = heights(&data);
+    assert_eq!((image.width, image.height), (5, 5));
+    assert_eq!(image.pixels[3], [198; 3]);
+
+    let mut ppm = vec![];
+    image.write_ppm(&mut ppm).unwrap();
+    assert!(ppm.starts_with(b"P6\n5 5\n255\n"));
+    assert_eq!(ppm.len(), 11 + 75);
+
+    let mut png = vec![];
+    image.write_png(&mut png).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x05\0\0\0\x05\x08\x02"));
+    assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+}
+
+#[test]
+fn can_render_overlays() {
+    let data = super::parse(include_str!("../example.txt")).unwrap();
+    let image = visible(&data, &super::visible(&data));
+    assert_eq!(image.pixels.iter().filter(|p| **p == VISIBLE).count(), 21);
+    assert_eq!(image.pixels[5 + 3], [14; 3]);
+
+    let image = scores(&data);
+    assert_eq!(image.pixels[3 * 5 + 2], [255; 3]);
+    assert_eq!(image.pixels[0], [0; 3]);
+}
@@ -1,9 +1,14 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
-fn visible_left(heights: &[u8]) -> Vec<usize> {
+mod image;
+mod placement;
+mod viewshed;
+
+#[cfg(test)]
+fn visible_left<H: Copy + Ord>(heights: &[H]) -> Vec<usize> {
     let mut trees = vec![0];
     heights
-        .into_iter()
+        .iter()
         .enumerate()
         .fold(heights[0], |acc, (index, item)| {
             if *item > acc {
@@ -16,10 +21,11 @@ fn visible_left(heights: &[u8]) -> Vec<usize> {
     trees
 }
 
-fn visible_right(heights: &[u8]) -> Vec<usize> {
+#[cfg(test)]
+fn visible_right<H: Copy + Ord>(heights: &[H]) -> Vec<usize> {
     let mut trees = vec![heights.len() - 1];
     heights
-        .into_iter()
+        .iter()
         .enumerate()
         .rev()
         .fold(*heights.last().unwrap(), |acc, (index, item)| {
@@ -33,53 +39,253 @@ fn visible_right(heights: &[u8]) -> Vec<usize> {
     trees
 }
 
-fn visible_sides(heights: &Vec<Vec<u8>>) -> BTreeSet<(usize, usize)> {
-    heights
+// steps (dx, dy) between neighbouring trees along a line of sight
+type Direction = (isize, isize);
+
+const AXES: [Direction; 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const DIAGONALS: [Direction; 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+const KNIGHT: [Direction; 8] = [
+    (1, -2),
+    (2, -1),
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    // the direction in which all trees have to be shorter
+    fn direction(&self) -> Direction {
+        match self {
+            Edge::Left => AXES[0],
+            Edge::Right => AXES[1],
+            Edge::Top => AXES[2],
+            Edge::Bottom => AXES[3],
+        }
+    }
+}
+
+fn step<H>(data: &[Vec<H>], (x, y): (usize, usize), (dx, dy): Direction) -> Option<(usize, usize)> {
+    let x = x.checked_add_signed(dx)?;
+    let y = y.checked_add_signed(dy)?;
+    (y < data.len() && x < data[y].len()).then_some((x, y))
+}
+
+// every line of sight looking in `direction`, each one starting with the
+// tree that is furthest along it and walking back from there.
+fn sight_lines<H>(data: &[Vec<H>], direction: Direction) -> Vec<Vec<(usize, usize)>> {
+    assert_ne!(direction, (0, 0));
+    let back = (-direction.0, -direction.1);
+    let ends = data
         .iter()
         .enumerate()
-        .map(|(y, line)| {
-            visible_left(&line)
-                .into_iter()
-                .chain(visible_right(&line).into_iter())
-                .map(move |x| (x, y))
+        .flat_map(|(y, row)| (0..row.len()).map(move |x| (x, y)));
+    ends.filter(|end| step(data, *end, direction).is_none())
+        .map(|end| {
+            let mut line = vec![end];
+            while let Some(next) = step(data, *line.last().unwrap(), back) {
+                line.push(next);
+            }
+            line
         })
-        .flatten()
         .collect()
 }
 
-fn transpose(data: &Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+#[test]
+fn can_find_sight_lines() {
+    let data = parse("123\n456\n").unwrap();
+    assert_eq!(
+        sight_lines(&data, (1, 0)),
+        vec![vec![(2, 0), (1, 0), (0, 0)], vec![(2, 1), (1, 1), (0, 1)]]
+    );
+    assert_eq!(
+        sight_lines(&data, (1, 1)),
+        vec![
+            vec![(2, 0)],
+            vec![(0, 1)],
+            vec![(1, 1), (0, 0)],
+            vec![(2, 1), (1, 0)]
+        ]
+    );
+    let lines = sight_lines(&data, (-1, 2));
+    assert_eq!(lines.len(), 6);
+    assert!(lines.iter().all(|line| line.len() == 1));
+}
+
+// the trees that can be seen from outside the forest when looking against
+// `direction`, because every tree further along it is shorter.
+fn visible_from<H: Copy + Ord>(data: &[Vec<H>], direction: Direction) -> BTreeSet<(usize, usize)> {
+    let mut result = BTreeSet::new();
+    for line in sight_lines(data, direction) {
+        let mut tallest = None;
+        for (x, y) in line {
+            if tallest.map(|t| data[y][x] > t).unwrap_or(true) {
+                result.insert((x, y));
+                tallest = Some(data[y][x]);
+            }
+        }
+    }
+    result
+}
+
+fn visible_along<H: Copy + Ord>(
+    data: &[Vec<H>],
+    directions: &[Direction],
+) -> BTreeSet<(usize, usize)> {
+    directions
+        .iter()
+        .flat_map(|direction| visible_from(data, *direction))
+        .collect()
+}
+
+#[test]
+fn can_check_visible_along() {
+    let data = parse(include_str!("../example.txt")).unwrap();
+    assert_eq!(visible_along(&data, &AXES), visible(&data));
+    let diagonal = visible_along(&data, &DIAGONALS);
+    assert_eq!(diagonal.len(), 22);
+    assert!(!diagonal.contains(&(2, 2)));
+    assert!(diagonal.contains(&(1, 2)));
+    assert!(visible_along(&data, &KNIGHT).len() > 16);
+}
+
+#[cfg(test)]
+fn transpose<H: Copy>(data: &[Vec<H>]) -> Vec<Vec<H>> {
     (0..data[0].len())
         .map(|offset| data.iter().map(|line| line[offset]).collect())
         .collect()
 }
 
-fn visible(heights: &Vec<Vec<u8>>) -> BTreeSet<(usize, usize)> {
-    let mut result = visible_sides(heights);
-    for (y, x) in visible_sides(&transpose(heights)) {
-        result.insert((x, y));
+// the edges of the forest every visible tree can be seen from
+fn visibility<H: Copy + Ord>(heights: &[Vec<H>]) -> BTreeMap<(usize, usize), BTreeSet<Edge>> {
+    let mut result: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for edge in [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom] {
+        for position in visible_from(heights, edge.direction()) {
+            result.entry(position).or_default().insert(edge);
+        }
     }
     result
 }
 
-fn parse(data: &str) -> Vec<Vec<u8>> {
-    data.lines()
-        .map(|line| {
-            line.split("")
-                .filter(|s| !s.is_empty())
-                .map(|num| num.parse().unwrap())
-                .collect()
+#[test]
+fn can_check_visibility() {
+    let data = parse(include_str!("../example.txt")).unwrap();
+    let visibility = visibility(&data);
+    assert_eq!(visibility[&(0, 0)], BTreeSet::from([Edge::Left, Edge::Top]));
+    assert_eq!(visibility[&(1, 1)], BTreeSet::from([Edge::Left, Edge::Top]));
+    assert_eq!(
+        visibility[&(2, 1)],
+        BTreeSet::from([Edge::Top, Edge::Right])
+    );
+    assert_eq!(
+        visibility[&(2, 3)],
+        BTreeSet::from([Edge::Left, Edge::Bottom])
+    );
+    assert_eq!(visibility.get(&(3, 1)), None);
+}
+
+fn visible<H: Copy + Ord>(heights: &[Vec<H>]) -> BTreeSet<(usize, usize)> {
+    visibility(heights).into_keys().collect()
+}
+
+// either one digit per tree, or heights separated by commas or whitespace.
+// whitespace around a line does not count as a separator, so a file without
+// any separator is always digits: `100\n200\n` is a 2x3 grid. a single column
+// of taller trees needs a trailing comma, like `100,\n200,\n`.
+fn parse(data: &str) -> Result<Vec<Vec<u16>>, String> {
+    let separated = data
+        .lines()
+        .any(|line| line.trim().contains([',', ' ', '\t']));
+    let rows: Vec<Vec<u16>> = data
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let invalid = |height: &str| format!("line {}: invalid height {height:?}", index + 1);
+            if separated {
+                line.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|s| !s.is_empty())
+                    .map(|height| height.parse().map_err(|_| invalid(height)))
+                    .collect()
+            } else {
+                line.trim()
+                    .chars()
+                    .map(|c| {
+                        c.to_digit(10)
+                            .map(|d| d as u16)
+                            .ok_or_else(|| invalid(&c.to_string()))
+                    })
+                    .collect()
+            }
         })
-        .collect()
+        .collect::<Result<_, _>>()?;
+    if rows.is_empty() {
+        return Err("no trees".into());
+    }
+    if let Some(row) = rows.iter().position(|row| row.len() != rows[0].len()) {
+        return Err(format!(
+            "row {} has {} trees, expected {}",
+            row + 1,
+            rows[row].len(),
+            rows[0].len()
+        ));
+    }
+    Ok(rows)
+}
+
+#[test]
+fn can_parse_encodings() {
+    let digits = parse("30373\n25512\n").unwrap();
+    assert_eq!(parse("3,0,3,7,3\n2,5,5,1,2\n").unwrap(), digits);
+    assert_eq!(parse("3 0 3  7 3\n\t2 5 5 1 2\n\n").unwrap(), digits);
+    assert_eq!(
+        parse("100, 2000\n65535, 0\n").unwrap(),
+        vec![vec![100, 2000], vec![65535, 0]]
+    );
+    assert_eq!(parse("30373 \n\t25512\n").unwrap(), digits);
+    assert_eq!(
+        parse("100\n200\n").unwrap(),
+        vec![vec![1, 0, 0], vec![2, 0, 0]]
+    );
+    assert_eq!(parse("100,\n200,\n").unwrap(), vec![vec![100], vec![200]]);
+    // mixing both encodings leaves rows of different lengths
+    assert!(parse("30373\n2 5 5 1 2\n").is_err());
+    assert!(parse("65536 1\n").is_err());
+    assert!(parse("1 2\n3\n").is_err());
+    assert!(parse("12x\n").is_err());
+    assert!(parse("-1,2\n").is_err());
+    assert!(parse("\n").is_err());
 }
 
-fn solve(data: &Vec<Vec<u8>>) -> usize {
+#[test]
+fn can_solve_tall_trees() {
+    let data = parse("10 1000 10\n500 499 700\n10 998 10\n").unwrap();
+    assert_eq!(solve(&data), 8);
+    assert_eq!(solve_two(&data), 1);
+    let narrow: Vec<Vec<u8>> = vec![vec![1, 9, 1], vec![5, 4, 7], vec![1, 7, 1]];
+    assert_eq!(solve(&narrow), 8);
+    assert_eq!(solve_two(&narrow), 1);
+}
+
+fn solve<H: Copy + Ord>(data: &[Vec<H>]) -> usize {
     visible(data).len()
 }
 
-fn scenic_score_right(row: &[u8], pos: usize) -> u64 {
+#[cfg(test)]
+fn scenic_score_right<H: Copy + Ord>(row: &[H], pos: usize) -> u64 {
     assert!(pos <= row.len());
     let trees = row
-        .into_iter()
+        .iter()
         .skip(pos + 1)
         .take_while(|e| **e < row[pos])
         .count();
@@ -101,10 +307,11 @@ fn can_compute_scenic_score_right() {
     assert_eq!(scenic_score_right(&[3, 3, 5, 4, 9], 4), 0);
 }
 
-fn scenic_score_left(row: &[u8], pos: usize) -> u64 {
+#[cfg(test)]
+fn scenic_score_left<H: Copy + Ord>(row: &[H], pos: usize) -> u64 {
     assert!(pos <= row.len());
     let trees = row
-        .into_iter()
+        .iter()
         .rev()
         .skip(row.len() - pos)
         .take_while(|e| **e < row[pos])
@@ -126,10 +333,11 @@ fn can_compute_scenic_score_left() {
     assert_eq!(scenic_score_left(&[3, 3, 5, 4, 9], 4), 4);
 }
 
-fn scenic_score(row: &[u8], col: &[u8], x: usize, y: usize) -> u64 {
+#[cfg(test)]
+fn scenic_score<H: Copy + Ord>(row: &[H], col: &[H], x: usize, y: usize) -> u64 {
     assert!(x < row.len());
     assert!(y < col.len());
-    assert_eq!(row[x], col[y]);
+    assert!(row[x] == col[y]);
 
     [
         scenic_score_left(row, x),
@@ -147,22 +355,194 @@ fn can_compute_scenic_score() {
     assert_eq!(scenic_score(&[3, 3, 5, 4, 9], &[3, 5, 3, 5, 3], 2, 3), 8);
 }
 
-fn solve_two(data: &Vec<Vec<u8>>) -> u64 {
-    let transposed = transpose(data);
-    let (_x, _y, score) = data
-        .iter()
+// distance to the nearest tree in front that is at least as tall, or to the
+// edge, for the tree at `index` along a line of sight. the stack keeps the
+// trees seen so far that can still block a view, with decreasing heights.
+fn look<H: Copy + Ord>(stack: &mut Vec<(usize, H)>, index: usize, height: H) -> u64 {
+    while stack.last().map(|(_, h)| *h < height).unwrap_or(false) {
+        stack.pop();
+    }
+    let distance = index - stack.last().map(|(i, _)| *i).unwrap_or(0);
+    stack.push((index, height));
+    distance as u64
+}
+
+#[test]
+fn can_look() {
+    for row in [[2, 5, 5, 1, 2], [3, 3, 5, 4, 9], [3, 0, 3, 7, 3]] {
+        let mut stack = vec![];
+        for pos in 0..row.len() {
+            assert_eq!(
+                look(&mut stack, pos, row[pos]),
+                scenic_score_left(&row, pos)
+            );
+        }
+        let mut stack = vec![];
+        for pos in (0..row.len()).rev() {
+            let index = row.len() - 1 - pos;
+            assert_eq!(
+                look(&mut stack, index, row[pos]),
+                scenic_score_right(&row, pos)
+            );
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Views {
+    left: u64,
+    right: u64,
+    up: u64,
+    down: u64,
+}
+
+impl Views {
+    fn score(&self) -> u64 {
+        self.left * self.right * self.up * self.down
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Spot {
+    x: usize,
+    y: usize,
+    views: Views,
+}
+
+// distance every tree can see when looking in `direction`
+fn view_distances<H: Copy + Ord>(data: &[Vec<H>], direction: Direction) -> Vec<Vec<u64>> {
+    let mut distances: Vec<Vec<u64>> = data.iter().map(|row| vec![0; row.len()]).collect();
+    let mut stack = vec![];
+    for line in sight_lines(data, direction) {
+        stack.clear();
+        for (index, (x, y)) in line.into_iter().enumerate() {
+            distances[y][x] = look(&mut stack, index, data[y][x]);
+        }
+    }
+    distances
+}
+
+fn scenic_scores<H: Copy + Ord>(data: &[Vec<H>], directions: &[Direction]) -> Vec<Vec<u64>> {
+    let mut scores: Vec<Vec<u64>> = data.iter().map(|row| vec![1; row.len()]).collect();
+    for direction in directions {
+        let distances = view_distances(data, *direction);
+        for (scores, distances) in scores.iter_mut().zip(distances) {
+            for (score, distance) in scores.iter_mut().zip(distances) {
+                *score *= distance;
+            }
+        }
+    }
+    scores
+}
+
+#[cfg(test)]
+fn view_distance<H: Copy + Ord>(
+    data: &[Vec<H>],
+    start: (usize, usize),
+    direction: Direction,
+) -> u64 {
+    let mut position = start;
+    let mut distance = 0;
+    while let Some(next) = step(data, position, direction) {
+        distance += 1;
+        if data[next.1][next.0] >= data[start.1][start.0] {
+            break;
+        }
+        position = next;
+    }
+    distance
+}
+
+#[test]
+fn can_compute_view_distances() {
+    let data = parse(include_str!("../example.txt")).unwrap();
+    for direction in AXES.iter().chain(&DIAGONALS).chain(&KNIGHT) {
+        let distances = view_distances(&data, *direction);
+        for (y, row) in distances.iter().enumerate() {
+            for (x, distance) in row.iter().enumerate() {
+                assert_eq!(*distance, view_distance(&data, (x, y), *direction));
+            }
+        }
+    }
+    let diagonal = scenic_scores(&data, &DIAGONALS);
+    assert_eq!(diagonal[2][2], 2);
+    assert_eq!(diagonal[1][1], 3);
+}
+
+fn views<H: Copy + Ord>(data: &[Vec<H>]) -> Vec<Vec<Views>> {
+    let [left, right, up, down] = AXES.map(|direction| view_distances(data, direction));
+    (0..data.len())
+        .map(|y| {
+            (0..data[y].len())
+                .map(|x| Views {
+                    left: left[y][x],
+                    right: right[y][x],
+                    up: up[y][x],
+                    down: down[y][x],
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn can_compute_views() {
+    let data = parse(include_str!("../example.txt")).unwrap();
+    let transposed = transpose(&data);
+    let views = views(&data);
+    for (y, row) in data.iter().enumerate() {
+        for (x, col) in transposed.iter().enumerate() {
+            let view = views[y][x];
+            assert_eq!(view.left, scenic_score_left(row, x));
+            assert_eq!(view.right, scenic_score_right(row, x));
+            assert_eq!(view.up, scenic_score_left(col, y));
+            assert_eq!(view.down, scenic_score_right(col, y));
+            assert_eq!(view.score(), scenic_score(row, col, x, y));
+        }
+    }
+}
+
+// every spot sharing the highest scenic score, in reading order
+fn best_spots<H: Copy + Ord>(data: &[Vec<H>]) -> Vec<Spot> {
+    let spots: Vec<Spot> = views(data)
+        .into_iter()
         .enumerate()
-        .map(|(y, row)| {
-            transposed
-                .iter()
+        .flat_map(|(y, row)| {
+            row.into_iter()
                 .enumerate()
-                .map(|(x, col)| (x, y, scenic_score(&row, &col, x, y)))
-                .max_by_key(|(_, _, score)| *score)
-                .unwrap()
+                .map(move |(x, views)| Spot { x, y, views })
         })
-        .max_by_key(|(_, _, score)| *score)
-        .unwrap();
-    score
+        .collect();
+    let best = spots.iter().map(|spot| spot.views.score()).max().unwrap();
+    spots
+        .into_iter()
+        .filter(|spot| spot.views.score() == best)
+        .collect()
+}
+
+#[test]
+fn can_find_best_spots() {
+    let data = parse(include_str!("../example.txt")).unwrap();
+    let views = Views {
+        left: 2,
+        right: 2,
+        up: 2,
+        down: 1,
+    };
+    assert_eq!(best_spots(&data), vec![Spot { x: 2, y: 3, views }]);
+
+    let data = parse("000\n000\n").unwrap();
+    let spots = best_spots(&data);
+    assert_eq!(spots.len(), 6);
+    assert!(spots.iter().all(|spot| spot.views.score() == 0));
+
+    let data = parse("11111\n10001\n10101\n10001\n11111\n").unwrap();
+    let spots: Vec<_> = best_spots(&data).iter().map(|s| (s.x, s.y)).collect();
+    assert_eq!(spots, vec![(2, 2)]);
+}
+
+fn solve_two<H: Copy + Ord>(data: &[Vec<H>]) -> u64 {
+    best_spots(data)[0].views.score()
 }
 
 #[test]
@@ -181,7 +561,7 @@ fn can_check_visible_right() {
 
 #[test]
 fn can_parse() {
-    let data = parse(include_str!("../example.txt"));
+    let data = parse(include_str!("../example.txt")).unwrap();
     assert_eq!(data[0], vec![3, 0, 3, 7, 3]);
     assert_eq!(data[1], vec![2, 5, 5, 1, 2]);
     assert_eq!(data[2], vec![6, 5, 3, 3, 2]);
@@ -189,7 +569,7 @@ fn can_parse() {
 
 #[test]
 fn can_check_visible() {
-    let data = parse(include_str!("../example.txt"));
+    let data = parse(include_str!("../example.txt")).unwrap();
     let vis = visible(&data);
     assert_eq!(vis.len(), 21);
 }
@@ -197,33 +577,136 @@ fn can_check_visible() {
 #[test]
 fn can_transpose() {
     assert_eq!(
-        transpose(&vec![vec![1, 2], vec![3, 4]]),
+        transpose(&[vec![1, 2], vec![3, 4]]),
         vec![vec![1, 3], vec![2, 4]]
     );
     assert_eq!(
-        transpose(&vec![
-            vec![1, 2, 3],
-            vec![4, 5, 6],
-            vec![7, 8, 9],
-            vec![0, 0, 0]
-        ]),
+        transpose(&[vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9], vec![0, 0, 0]]),
         vec![vec![1, 4, 7, 0], vec![2, 5, 8, 0], vec![3, 6, 9, 0]]
     );
 }
 
 #[test]
 fn can_solve_two() {
-    let data = parse(include_str!("../example.txt"));
+    let data = parse(include_str!("../example.txt")).unwrap();
     let score = solve_two(&data);
     assert_eq!(score, 8);
 }
 
+// named sets or single `dx,dy` steps, the axes if nothing is given
+fn parse_directions(args: &[String]) -> Result<Vec<Direction>, String> {
+    let mut directions = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "axes" => directions.extend(AXES),
+            "diagonals" => directions.extend(DIAGONALS),
+            "knight" => directions.extend(KNIGHT),
+            other => {
+                let direction = other
+                    .split_once(',')
+                    .and_then(|(dx, dy)| Some((dx.parse().ok()?, dy.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid direction {other}"))?;
+                if direction == (0, 0) {
+                    return Err("direction must not be zero".into());
+                }
+                directions.push(direction);
+            }
+        }
+    }
+    if directions.is_empty() {
+        directions.extend(AXES);
+    }
+    Ok(directions)
+}
+
+#[test]
+fn can_parse_directions() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(parse_directions(&[]), Ok(AXES.to_vec()));
+    assert_eq!(
+        parse_directions(&args(&["diagonals", "3,-1"])),
+        Ok(vec![(-1, -1), (1, -1), (-1, 1), (1, 1), (3, -1)])
+    );
+    assert_eq!(parse_directions(&args(&["knight"])).unwrap().len(), 8);
+    assert!(parse_directions(&args(&["0,0"])).is_err());
+    assert!(parse_directions(&args(&["up"])).is_err());
+}
+
 fn main() {
     let file = std::env::args().nth(1).unwrap();
     let data = std::fs::read_to_string(file).unwrap();
-    let data = parse(&data);
+    let data = parse(&data).unwrap();
     let result = solve(&data);
     println!("{result}");
     let score = solve_two(&data);
     println!("{score}");
+    match std::env::args().nth(2).as_deref() {
+        Some("spots") => {
+            for Spot { x, y, views } in best_spots(&data) {
+                println!(
+                    "{x},{y}: left {}, right {}, up {}, down {}",
+                    views.left, views.right, views.up, views.down
+                );
+            }
+        }
+        Some("visible") => {
+            for ((x, y), edges) in visibility(&data) {
+                println!("{x},{y}: {edges:?}");
+            }
+        }
+        Some("directions") => {
+            let args: Vec<String> = std::env::args().skip(3).collect();
+            let directions = parse_directions(&args).unwrap();
+            println!("{}", visible_along(&data, &directions).len());
+            let scores = scenic_scores(&data, &directions);
+            println!("{}", scores.iter().flatten().max().unwrap());
+        }
+        Some("viewshed") => {
+            let number = |index: usize| std::env::args().nth(index).unwrap();
+            let observer = viewshed::Observer {
+                x: number(3).parse().unwrap(),
+                y: number(4).parse().unwrap(),
+                height: number(5).parse().unwrap(),
+            };
+            match viewshed::viewshed(&data, &observer) {
+                Ok(visible) => {
+                    println!("{}", visible.len());
+                    print!("{}", viewshed::render(&data, &visible, &observer));
+                }
+                Err(error) => eprintln!("{error}"),
+            }
+        }
+        Some("treehouses") => {
+            let number = |index: usize| std::env::args().nth(index).unwrap().parse().unwrap();
+            let scores = scenic_scores(&data, &AXES);
+            match placement::place(&scores, number(3), number(4)) {
+                Ok(placement) => {
+                    println!("{}", placement.total);
+                    if !placement.exact {
+                        println!("(not proven to be the best placement)");
+                    }
+                    for (x, y) in placement.spots {
+                        println!("{x},{y}: {}", scores[y][x]);
+                    }
+                }
+                Err(error) => eprintln!("{error}"),
+            }
+        }
+        Some("heatmap") => {
+            let args: Vec<String> = std::env::args().skip(3).collect();
+            let image = match args[0].as_str() {
+                "heights" => image::heights(&data),
+                "visible" => image::visible(&data, &visible(&data)),
+                "scores" => image::scores(&data),
+                other => panic!("unknown heatmap {other}"),
+            };
+            let file = std::io::BufWriter::new(std::fs::File::create(&args[1]).unwrap());
+            if args[1].ends_with(".png") {
+                image.write_png(file).unwrap();
+            } else {
+                image.write_ppm(file).unwrap();
+            }
+        }
+        _ => {}
+    }
 }
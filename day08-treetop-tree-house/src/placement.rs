@@ -0,0 +1,306 @@
+// above this many treehouses the exact search gets too slow on big forests
+pub const EXACT_LIMIT: usize = 4;
+// the exact search gives up after this many steps, keeping the best
+// placement found so far without knowing whether it is the best one.
+const MAX_STEPS: usize = 10_000_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub total: u64,
+    pub spots: Vec<(usize, usize)>,
+    // whether no other placement can score higher
+    pub exact: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unplaced {
+    // there is no way to place that many treehouses
+    Infeasible,
+    // no placement was found, but there may be one
+    GaveUp,
+}
+
+impl std::fmt::Display for Unplaced {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unplaced::Infeasible => write!(f, "that many treehouses do not fit this far apart"),
+            Unplaced::GaveUp => write!(f, "gave up searching for a placement"),
+        }
+    }
+}
+
+fn manhattan(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+// every tree with its score, best first
+fn candidates(scores: &[Vec<u64>]) -> Vec<((usize, usize), u64)> {
+    let mut candidates: Vec<_> = scores
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, s)| ((x, y), *s)))
+        .collect();
+    candidates.sort_by_key(|(position, score)| (std::cmp::Reverse(*score), *position));
+    candidates
+}
+
+fn fits(spots: &[(usize, usize)], position: (usize, usize), spacing: usize) -> bool {
+    spots
+        .iter()
+        .all(|spot| *spot != position && manhattan(*spot, position) >= spacing)
+}
+
+// any two trees in a block of `spacing / 2` (rounded up) squares are closer
+// than `spacing`, so no placement has more treehouses than there are blocks.
+fn capacity(scores: &[Vec<u64>], spacing: usize) -> usize {
+    let side = spacing.div_ceil(2).max(1);
+    let width = scores.first().map(|row| row.len()).unwrap_or(0);
+    width.div_ceil(side) * scores.len().div_ceil(side)
+}
+
+fn placement(spots: Vec<(usize, usize)>, scores: &[Vec<u64>], exact: bool) -> Placement {
+    let total = spots.iter().map(|(x, y)| scores[*y][*x]).sum();
+    Placement {
+        total,
+        spots,
+        exact,
+    }
+}
+
+struct Search<'a> {
+    candidates: &'a [((usize, usize), u64)],
+    // prefix[i] is the sum of the first i candidate scores
+    prefix: Vec<u64>,
+    count: usize,
+    spacing: usize,
+    steps: usize,
+    gave_up: bool,
+    chosen: Vec<(usize, usize)>,
+    best: Option<(u64, Vec<(usize, usize)>)>,
+}
+
+impl Search<'_> {
+    fn run(&mut self, start: usize, total: u64) {
+        if self.chosen.len() == self.count {
+            if self.best.as_ref().map(|(b, _)| total > *b).unwrap_or(true) {
+                self.best = Some((total, self.chosen.clone()));
+            }
+            return;
+        }
+        let left = self.count - self.chosen.len();
+        for index in start..self.candidates.len() {
+            if self.steps == 0 {
+                self.gave_up = true;
+                return;
+            }
+            self.steps -= 1;
+            // candidates are sorted, so no later one can do better either
+            let end = (index + left).min(self.candidates.len());
+            let bound = total + self.prefix[end] - self.prefix[index];
+            if self
+                .best
+                .as_ref()
+                .map(|(b, _)| bound <= *b)
+                .unwrap_or(false)
+            {
+                break;
+            }
+            let (position, score) = self.candidates[index];
+            if fits(&self.chosen, position, self.spacing) {
+                self.chosen.push(position);
+                self.run(index + 1, total + score);
+                self.chosen.pop();
+            }
+        }
+    }
+}
+
+// branch and bound over the trees in order of their score
+pub fn exact(scores: &[Vec<u64>], count: usize, spacing: usize) -> Result<Placement, Unplaced> {
+    if count > capacity(scores, spacing) {
+        return Err(Unplaced::Infeasible);
+    }
+    let candidates = candidates(scores);
+    let prefix = std::iter::once(0)
+        .chain(candidates.iter().scan(0, |sum, (_, score)| {
+            *sum += score;
+            Some(*sum)
+        }))
+        .collect();
+    let mut search = Search {
+        candidates: &candidates,
+        prefix,
+        count,
+        spacing,
+        steps: MAX_STEPS,
+        gave_up: false,
+        chosen: vec![],
+        best: heuristic(scores, count, spacing).map(|p| (p.total, p.spots)),
+    };
+    search.run(0, 0);
+    match (search.best, search.gave_up) {
+        (Some((_, spots)), gave_up) => Ok(placement(spots, scores, !gave_up)),
+        (None, false) => Err(Unplaced::Infeasible),
+        (None, true) => Err(Unplaced::GaveUp),
+    }
+}
+
+// greedily takes the best tree that still fits, then keeps moving single
+// treehouses to better spots until that stops paying off.
+pub fn heuristic(scores: &[Vec<u64>], count: usize, spacing: usize) -> Option<Placement> {
+    let candidates = candidates(scores);
+    let mut spots = vec![];
+    for (position, _) in &candidates {
+        if spots.len() == count {
+            break;
+        }
+        if fits(&spots, *position, spacing) {
+            spots.push(*position);
+        }
+    }
+    if spots.len() < count {
+        return None;
+    }
+
+    let score = |(x, y): (usize, usize)| scores[y][x];
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for index in 0..spots.len() {
+            let current = spots.remove(index);
+            let better = candidates
+                .iter()
+                .take_while(|(_, s)| *s > score(current))
+                .find(|(position, _)| fits(&spots, *position, spacing));
+            match better {
+                Some((position, _)) => {
+                    spots.insert(index, *position);
+                    improved = true;
+                }
+                None => spots.insert(index, current),
+            }
+        }
+    }
+    Some(placement(spots, scores, false))
+}
+
+pub fn place(scores: &[Vec<u64>], count: usize, spacing: usize) -> Result<Placement, Unplaced> {
+    if count <= EXACT_LIMIT {
+        exact(scores, count, spacing)
+    } else if count > capacity(scores, spacing) {
+        Err(Unplaced::Infeasible)
+    } else {
+        heuristic(scores, count, spacing).ok_or(Unplaced::GaveUp)
+    }
+}
+
+#[cfg(test)]
+fn brute_force(scores: &[Vec<u64>], count: usize, spacing: usize) -> Option<u64> {
+    fn go(
+        candidates: &[((usize, usize), u64)],
+        chosen: &mut Vec<(usize, usize)>,
+        count: usize,
+        spacing: usize,
+        total: u64,
+    ) -> Option<u64> {
+        if chosen.len() == count {
+            return Some(total);
+        }
+        let mut best = None;
+        for (index, (position, score)) in candidates.iter().enumerate() {
+            if fits(chosen, *position, spacing) {
+                chosen.push(*position);
+                let result = go(
+                    &candidates[index + 1..],
+                    chosen,
+                    count,
+                    spacing,
+                    total + score,
+                );
+                chosen.pop();
+                best = best.max(result);
+            }
+        }
+        best
+    }
+    go(&candidates(scores), &mut vec![], count, spacing, 0)
+}
+
+#[test]
+fn can_place_exactly() {
+    // greedy takes the 9 and is then stuck with the corners
+    let scores = vec![vec![1, 8, 1], vec![0, 9, 0], vec![1, 8, 1]];
+    assert_eq!(heuristic(&scores, 2, 2).unwrap().total, 10);
+    assert!(!heuristic(&scores, 2, 2).unwrap().exact);
+    let placement = exact(&scores, 2, 2).unwrap();
+    assert_eq!(placement.total, 16);
+    assert_eq!(placement.spots, vec![(1, 0), (1, 2)]);
+    assert!(placement.exact);
+    assert_eq!(exact(&scores, 5, 2).unwrap().total, 13);
+    assert_eq!(exact(&scores, 6, 2), Err(Unplaced::Infeasible));
+    assert_eq!(exact(&scores, 0, 2).unwrap().total, 0);
+}
+
+#[test]
+fn can_place_without_spacing() {
+    // every treehouse still needs a tree of its own
+    let scores = vec![vec![9, 8, 1]];
+    for placement in [heuristic(&scores, 2, 0), exact(&scores, 2, 0).ok()] {
+        let placement = placement.unwrap();
+        assert_eq!(placement.total, 17);
+        assert_eq!(placement.spots, vec![(0, 0), (1, 0)]);
+    }
+    assert_eq!(heuristic(&scores, 4, 0), None);
+    assert_eq!(exact(&scores, 4, 0), Err(Unplaced::Infeasible));
+}
+
+#[test]
+fn can_bound_infeasible_searches() {
+    let scores = vec![vec![1; 200]; 200];
+    assert_eq!(capacity(&scores, 0), 40000);
+    assert_eq!(capacity(&scores, 399), 1);
+    assert_eq!(exact(&scores, 2, 399), Err(Unplaced::Infeasible));
+    assert_eq!(place(&scores, 5, 399), Err(Unplaced::Infeasible));
+    // fits the blocks, but no four trees are that far apart. the heuristic
+    // gives up, and the search stops after its step budget.
+    assert_eq!(capacity(&scores, 200), 4);
+    assert_eq!(exact(&scores, 4, 200), Err(Unplaced::GaveUp));
+    let placement = exact(&scores, 2, 200).unwrap();
+    assert_eq!(placement.total, 2);
+    assert!(placement.exact);
+}
+
+#[test]
+fn can_match_brute_force() {
+    let data = super::parse(include_str!("../example.txt")).unwrap();
+    let scores = super::scenic_scores(&data, &super::AXES);
+    for count in 1..=4 {
+        for spacing in 0..=4 {
+            let placement = exact(&scores, count, spacing);
+            assert!(placement.as_ref().map(|p| p.exact).unwrap_or(true));
+            assert_ne!(placement, Err(Unplaced::GaveUp));
+            let exact = placement.ok().map(|p| p.total);
+            assert_eq!(exact, brute_force(&scores, count, spacing));
+            let heuristic = heuristic(&scores, count, spacing).map(|p| p.total);
+            assert!(heuristic <= exact);
+        }
+    }
+}
+
+#[test]
+fn can_place_many() {
+    let scores: Vec<Vec<u64>> = (0..30)
+        .map(|y| (0..30).map(|x| (x * 7 + y * 13) % 17).collect())
+        .collect();
+    let placement = place(&scores, 20, 5).unwrap();
+    assert_eq!(placement.spots.len(), 20);
+    assert!(!placement.exact);
+    for (index, a) in placement.spots.iter().enumerate() {
+        for b in &placement.spots[index + 1..] {
+            assert!(manhattan(*a, *b) >= 5);
+        }
+    }
+    let total: u64 = placement.spots.iter().map(|(x, y)| scores[*y][*x]).sum();
+    assert_eq!(placement.total, total);
+    assert!(total >= 20 * 14);
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeSet;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observer {
+    pub x: usize,
+    pub y: usize,
+    // eye level above the top of the tree the observer stands on
+    pub height: f64,
+}
+
+// the squares on the way from one square to another, both ends included
+pub fn line((x0, y0): (usize, usize), (x1, y1): (usize, usize)) -> Vec<(usize, usize)> {
+    let (x0, y0, x1, y1) = (x0 as isize, y0 as isize, x1 as isize, y1 as isize);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    let mut line = vec![(x as usize, y as usize)];
+    while (x, y) != (x1, y1) {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+        line.push((x as usize, y as usize));
+    }
+    line
+}
+
+// a tree is visible if no tree in between rises above the straight line
+// from the eye of the observer to its top.
+pub fn viewshed<H: Copy + Into<f64>>(
+    data: &[Vec<H>],
+    observer: &Observer,
+) -> Result<BTreeSet<(usize, usize)>, String> {
+    let (x, y) = (observer.x, observer.y);
+    if data.get(y).and_then(|row| row.get(x)).is_none() {
+        return Err(format!("observer at {x},{y} is outside of the forest"));
+    }
+    let elevation = |(x, y): (usize, usize)| -> f64 { data[y][x].into() };
+    let eye = elevation((x, y)) + observer.height;
+    let slope = |target: (usize, usize)| {
+        let distance = (target.0 as f64 - x as f64).hypot(target.1 as f64 - y as f64);
+        (elevation(target) - eye) / distance
+    };
+
+    let mut visible = BTreeSet::from([(x, y)]);
+    for (ty, row) in data.iter().enumerate() {
+        for tx in 0..row.len() {
+            let path = line((x, y), (tx, ty));
+            if path.len() < 2 {
+                continue;
+            }
+            let target = slope((tx, ty));
+            let blocked = path[1..path.len() - 1]
+                .iter()
+                .any(|square| slope(*square) > target);
+            if !blocked {
+                visible.insert((tx, ty));
+            }
+        }
+    }
+    Ok(visible)
+}
+
+pub fn render(
+    data: &[Vec<impl Copy>],
+    visible: &BTreeSet<(usize, usize)>,
+    observer: &Observer,
+) -> String {
+    let mut output = String::new();
+    for (y, row) in data.iter().enumerate() {
+        for x in 0..row.len() {
+            output.push(match (x, y) {
+                _ if (x, y) == (observer.x, observer.y) => '@',
+                position if visible.contains(&position) => '#',
+                _ => '.',
+            });
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[test]
+fn can_draw_lines() {
+    assert_eq!(line((0, 0), (0, 0)), vec![(0, 0)]);
+    assert_eq!(line((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+    assert_eq!(line((2, 2), (0, 0)), vec![(2, 2), (1, 1), (0, 0)]);
+    assert_eq!(line((0, 0), (3, 1)), vec![(0, 0), (1, 0), (2, 1), (3, 1)]);
+    assert_eq!(line((3, 1), (0, 0)), vec![(3, 1), (2, 1), (1, 0), (0, 0)]);
+    assert_eq!(line((1, 3), (1, 0)), vec![(1, 3), (1, 2), (1, 1), (1, 0)]);
+}
+
+#[test]
+fn can_see_over_flat_terrain() {
+    let data = vec![vec![3u8; 6]; 4];
+    let observer = Observer {
+        x: 2,
+        y: 1,
+        height: 0.0,
+    };
+    assert_eq!(viewshed(&data, &observer).unwrap().len(), 24);
+}
+
+#[test]
+fn can_be_blocked_by_walls() {
+    let data: Vec<Vec<u8>> = vec![vec![0, 0, 5, 0, 0], vec![0, 0, 5, 0, 20]];
+    let low = Observer {
+        x: 0,
+        y: 0,
+        height: 1.0,
+    };
+    let visible = viewshed(&data, &low).unwrap();
+    assert!(!visible.contains(&(3, 0)));
+    assert!(visible.contains(&(4, 1)));
+    assert_eq!(render(&data, &visible, &low), "@##..\n###.#\n");
+
+    let high = Observer {
+        height: 20.0,
+        ..low
+    };
+    assert_eq!(viewshed(&data, &high).unwrap().len(), 10);
+    let outside = Observer { x: 5, ..low };
+    assert!(viewshed(&data, &outside).is_err());
+}
+
+#[test]
+fn can_compute_example_viewshed() {
+    let data = super::parse(include_str!("../example.txt")).unwrap();
+    let observer = Observer {
+        x: 2,
+        y: 3,
+        height: 0.5,
+    };
+    let visible = viewshed(&data, &observer).unwrap();
+    assert!(visible.contains(&(2, 4)));
+    assert!(visible.contains(&(4, 3)));
+    assert!(visible.contains(&(0, 3)));
+    assert!(!visible.contains(&(2, 0)));
+}
@@ -0,0 +1,119 @@
+use super::Instruction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split(';').next().unwrap_or("").trim()
+}
+
+fn is_jump(mnemonic: &str) -> bool {
+    matches!(mnemonic, "jmp" | "jz" | "jnz" | "jlz" | "jgz")
+}
+
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssembleError> {
+    let mut labels = BTreeMap::new();
+    let mut statements = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let mut line = strip_comment(line);
+        while let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return Err(AssembleError {
+                    line: number,
+                    message: format!("invalid label {label:?}"),
+                });
+            }
+            if labels.insert(label, statements.len()).is_some() {
+                return Err(AssembleError {
+                    line: number,
+                    message: format!("duplicate label {label}"),
+                });
+            }
+            line = rest.trim();
+        }
+        if !line.is_empty() {
+            statements.push((number, line));
+        }
+    }
+
+    statements
+        .into_iter()
+        .map(|(number, line)| {
+            let mut words: Vec<&str> = line.split_whitespace().collect();
+            let target;
+            if is_jump(words[0]) && words.len() > 1 {
+                let last = words.len() - 1;
+                if let Some(address) = labels.get(words[last]) {
+                    target = address.to_string();
+                    words[last] = &target;
+                }
+            }
+            words.join(" ").parse().map_err(|message| AssembleError {
+                line: number,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn can_assemble_puzzle_programs() {
+    let data = include_str!("../example.txt");
+    assert_eq!(assemble(data), Ok(super::parse(data)));
+}
+
+#[test]
+fn can_assemble_labels() {
+    use super::{Condition, Operand, Register};
+    let source = "\
+        mov a 3 ; loop counter
+loop:   addx 2
+        sub a 1
+        jnz a loop
+end:
+";
+    assert_eq!(
+        assemble(source),
+        Ok(vec![
+            Instruction::Mov(Register::A, Operand::Value(3)),
+            Instruction::AddX(2),
+            Instruction::Sub(Register::A, Operand::Value(1)),
+            Instruction::Jump(Condition::NotZero(Register::A), 1),
+        ])
+    );
+}
+
+#[test]
+fn can_report_errors() {
+    assert_eq!(
+        assemble("noop\njmp nowhere\n"),
+        Err(AssembleError {
+            line: 2,
+            message: "error parsing jmp target: invalid digit found in string".into(),
+        })
+    );
+    assert_eq!(
+        assemble("a: noop\na: noop\n"),
+        Err(AssembleError {
+            line: 2,
+            message: "duplicate label a".into(),
+        })
+    );
+    assert_eq!(
+        assemble("noop\n\nfoo 1\n").unwrap_err().to_string(),
+        "line 3: invalid instruction foo"
+    );
+}
@@ -1,81 +1,404 @@
-use std::cell::RefCell;
-use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
+mod assembler;
+mod ocr;
+mod synth;
+mod trace;
+mod vcd;
+
 const TIMES: &[u64] = &[20, 60, 100, 140, 180, 220];
+const WIDTH: usize = 40;
+const HEIGHT: usize = 6;
+// programs with jumps may never halt, so runs that are collected stop here
+const CYCLE_LIMIT: u64 = 1_000_000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Register {
+    X,
+    A,
+    B,
+    C,
+    D,
+}
+
+const REGISTERS: [Register; 5] = [
+    Register::X,
+    Register::A,
+    Register::B,
+    Register::C,
+    Register::D,
+];
+
+impl FromStr for Register {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "x" => Ok(Register::X),
+            "a" => Ok(Register::A),
+            "b" => Ok(Register::B),
+            "c" => Ok(Register::C),
+            "d" => Ok(Register::D),
+            other => Err(format!("invalid register {other}")),
+        }
+    }
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Register::X => "x",
+            Register::A => "a",
+            Register::B => "b",
+            Register::C => "c",
+            Register::D => "d",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Operand {
+    Register(Register),
+    Value(i64),
+}
+
+impl Operand {
+    fn value(&self, state: &State) -> i64 {
+        match self {
+            Operand::Register(register) => state.register(*register),
+            Operand::Value(value) => *value,
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = String;
+    fn from_str(word: &str) -> Result<Self, Self::Err> {
+        match word.parse() {
+            Ok(register) => Ok(Operand::Register(register)),
+            Err(_) => word
+                .parse()
+                .map(Operand::Value)
+                .map_err(|e| format!("error parsing operand {word}: {e}")),
+        }
+    }
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Register(register) => write!(f, "{register}"),
+            Operand::Value(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Condition {
+    Always,
+    Zero(Register),
+    NotZero(Register),
+    Negative(Register),
+    Positive(Register),
+}
+
+impl Condition {
+    fn holds(&self, state: &State) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Zero(register) => state.register(*register) == 0,
+            Condition::NotZero(register) => state.register(*register) != 0,
+            Condition::Negative(register) => state.register(*register) < 0,
+            Condition::Positive(register) => state.register(*register) > 0,
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum Instruction {
     AddX(i64),
     NoOp,
+    Mov(Register, Operand),
+    Add(Register, Operand),
+    Sub(Register, Operand),
+    Mul(Register, Operand),
+    Jump(Condition, usize),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Costs {
+    addx: u64,
+    noop: u64,
+    mov: u64,
+    arithmetic: u64,
+    jump: u64,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            addx: 2,
+            noop: 1,
+            mov: 1,
+            arithmetic: 1,
+            jump: 1,
+        }
+    }
+}
+
+impl Costs {
+    fn cycles(&self, instruction: &Instruction) -> u64 {
+        match instruction {
+            Instruction::AddX(_) => self.addx,
+            Instruction::NoOp => self.noop,
+            Instruction::Mov(_, _) => self.mov,
+            Instruction::Add(_, _) | Instruction::Sub(_, _) | Instruction::Mul(_, _) => {
+                self.arithmetic
+            }
+            Instruction::Jump(_, _) => self.jump,
+        }
+    }
+
+    // a free jump back to itself would spin without ever finishing a cycle
+    fn check(&self) -> Result<(), String> {
+        let costs = [
+            ("addx", self.addx),
+            ("noop", self.noop),
+            ("mov", self.mov),
+            ("arithmetic", self.arithmetic),
+            ("jump", self.jump),
+        ];
+        match costs.iter().find(|(_, cost)| *cost == 0) {
+            Some((name, _)) => Err(format!("{name} has to take at least one cycle")),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Instruction {
-    pub fn apply(&self, cpu: &mut Cpu) {
+    pub fn execute(&self, state: &mut State) -> Option<usize> {
         match self {
             Instruction::AddX(value) => {
-                cpu.tick();
-                cpu.tick();
-                cpu.x += value;
+                let x = &mut state.registers[Register::X as usize];
+                *x = x.wrapping_add(*value);
+            }
+            Instruction::NoOp => {}
+            Instruction::Mov(register, operand) => {
+                state.registers[*register as usize] = operand.value(state);
+            }
+            Instruction::Add(register, operand) => {
+                let value = operand.value(state);
+                let target = &mut state.registers[*register as usize];
+                *target = target.wrapping_add(value);
+            }
+            Instruction::Sub(register, operand) => {
+                let value = operand.value(state);
+                let target = &mut state.registers[*register as usize];
+                *target = target.wrapping_sub(value);
             }
-            Instruction::NoOp => {
-                cpu.tick();
+            Instruction::Mul(register, operand) => {
+                let value = operand.value(state);
+                let target = &mut state.registers[*register as usize];
+                *target = target.wrapping_mul(value);
+            }
+            Instruction::Jump(condition, target) => {
+                if condition.holds(state) {
+                    return Some(*target);
+                }
             }
         }
+        None
     }
 }
 
 impl FromStr for Instruction {
     type Err = String;
     fn from_str(line: &str) -> Result<Self, Self::Err> {
-        let mut words = line.split(" ");
+        let mut words = line.split(' ');
         let first = words
             .next()
             .ok_or_else(|| "missing instruction".to_string())?;
+        let mut argument = |name: &str| {
+            words
+                .next()
+                .ok_or_else(|| format!("missing {first} {name}"))
+        };
         match first {
-            "addx" => Ok(Instruction::AddX(
-                words
-                    .next()
-                    .ok_or_else(|| format!("missing addx argument"))?
-                    .parse()
-                    .map_err(|e| format!("error parsing addx argument: {e}"))?,
-            )),
+            "addx" => {
+                Ok(Instruction::AddX(argument("argument")?.parse().map_err(
+                    |e| format!("error parsing addx argument: {e}"),
+                )?))
+            }
             "noop" => Ok(Instruction::NoOp),
+            "mov" | "add" | "sub" | "mul" => {
+                let register = argument("register")?.parse()?;
+                let operand = argument("operand")?.parse()?;
+                Ok(match first {
+                    "mov" => Instruction::Mov(register, operand),
+                    "add" => Instruction::Add(register, operand),
+                    "sub" => Instruction::Sub(register, operand),
+                    _ => Instruction::Mul(register, operand),
+                })
+            }
+            "jmp" | "jz" | "jnz" | "jlz" | "jgz" => {
+                let condition = match first {
+                    "jmp" => Condition::Always,
+                    "jz" => Condition::Zero(argument("register")?.parse()?),
+                    "jnz" => Condition::NotZero(argument("register")?.parse()?),
+                    "jlz" => Condition::Negative(argument("register")?.parse()?),
+                    _ => Condition::Positive(argument("register")?.parse()?),
+                };
+                let target = argument("target")?
+                    .parse()
+                    .map_err(|e| format!("error parsing {first} target: {e}"))?;
+                Ok(Instruction::Jump(condition, target))
+            }
             other => Err(format!("invalid instruction {other}")),
         }
     }
 }
 
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::AddX(value) => write!(f, "addx {value}"),
+            Instruction::NoOp => write!(f, "noop"),
+            Instruction::Mov(register, operand) => write!(f, "mov {register} {operand}"),
+            Instruction::Add(register, operand) => write!(f, "add {register} {operand}"),
+            Instruction::Sub(register, operand) => write!(f, "sub {register} {operand}"),
+            Instruction::Mul(register, operand) => write!(f, "mul {register} {operand}"),
+            Instruction::Jump(condition, target) => match condition {
+                Condition::Always => write!(f, "jmp {target}"),
+                Condition::Zero(register) => write!(f, "jz {register} {target}"),
+                Condition::NotZero(register) => write!(f, "jnz {register} {target}"),
+                Condition::Negative(register) => write!(f, "jlz {register} {target}"),
+                Condition::Positive(register) => write!(f, "jgz {register} {target}"),
+            },
+        }
+    }
+}
+
+#[test]
+fn can_parse_instruction() {
+    use Instruction::*;
+    let lines = [
+        ("addx -4", AddX(-4)),
+        ("noop", NoOp),
+        ("mov a 3", Mov(Register::A, Operand::Value(3))),
+        ("add x b", Add(Register::X, Operand::Register(Register::B))),
+        ("sub c -2", Sub(Register::C, Operand::Value(-2))),
+        ("mul d x", Mul(Register::D, Operand::Register(Register::X))),
+        ("jmp 7", Jump(Condition::Always, 7)),
+        ("jz a 0", Jump(Condition::Zero(Register::A), 0)),
+        ("jnz b 2", Jump(Condition::NotZero(Register::B), 2)),
+        ("jlz c 3", Jump(Condition::Negative(Register::C), 3)),
+        ("jgz x 4", Jump(Condition::Positive(Register::X), 4)),
+    ];
+    for (line, instruction) in lines {
+        assert_eq!(line.parse(), Ok(instruction));
+        assert_eq!(instruction.to_string(), line);
+    }
+    assert!("mov y 1".parse::<Instruction>().is_err());
+    assert!("add a".parse::<Instruction>().is_err());
+    assert!("jnz a".parse::<Instruction>().is_err());
+}
+
 fn parse(data: &str) -> Vec<Instruction> {
     data.lines().map(|line| line.parse().unwrap()).collect()
 }
 
-struct Cpu {
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct State {
     cycle: u64,
-    x: i64,
-    callback: Box<dyn Fn(&Cpu)>,
+    pc: usize,
+    registers: [i64; REGISTERS.len()],
 }
 
-impl Cpu {
-    pub fn new(callback: Box<dyn Fn(&Cpu)>) -> Self {
-        Cpu {
+impl Default for State {
+    fn default() -> Self {
+        let mut registers = [0; REGISTERS.len()];
+        registers[Register::X as usize] = 1;
+        State {
             cycle: 0,
-            x: 1,
-            callback: callback,
+            pc: 0,
+            registers,
+        }
+    }
+}
+
+impl State {
+    pub fn register(&self, register: Register) -> i64 {
+        self.registers[register as usize]
+    }
+
+    pub fn x(&self) -> i64 {
+        self.register(Register::X)
+    }
+}
+
+struct Cpu<'a> {
+    state: State,
+    costs: Costs,
+    instructions: &'a [Instruction],
+    remaining: u64,
+    halted: bool,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(instructions: &'a [Instruction]) -> Self {
+        Cpu {
+            state: State::default(),
+            costs: Costs::default(),
+            instructions,
+            remaining: 0,
+            halted: false,
         }
     }
 
-    pub fn tick(&mut self) {
-        self.cycle += 1;
-        (self.callback)(&self);
+    pub fn with_costs(mut self, costs: Costs) -> Result<Self, String> {
+        costs.check()?;
+        self.costs = costs;
+        Ok(self)
+    }
+
+    fn execute(&mut self) {
+        let instruction = self.instructions[self.state.pc];
+        let next = self.state.pc + 1;
+        self.state.pc = instruction.execute(&mut self.state).unwrap_or(next);
+    }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = State;
+
+    fn next(&mut self) -> Option<State> {
+        if self.remaining == 0 {
+            match self.instructions.get(self.state.pc) {
+                Some(instruction) => self.remaining = self.costs.cycles(instruction),
+                // the cpu ticks once more after the program ends.
+                None if !self.halted => {
+                    self.halted = true;
+                    self.state.cycle += 1;
+                    return Some(self.state);
+                }
+                None => return None,
+            }
+        }
+        self.state.cycle += 1;
+        self.remaining -= 1;
+        let state = self.state;
+        if self.remaining == 0 {
+            self.execute();
+        }
+        Some(state)
     }
 }
 
 #[test]
 fn can_parse() {
     let data = include_str!("../example.txt");
-    let instructions = parse(&data);
+    let instructions = parse(data);
     assert_eq!(instructions[0], Instruction::AddX(15));
     assert_eq!(instructions[1], Instruction::AddX(-11));
     assert_eq!(instructions[2], Instruction::AddX(6));
@@ -83,36 +406,228 @@ fn can_parse() {
     assert_eq!(instructions[9], Instruction::NoOp);
 }
 
+#[test]
+fn can_run_extended_program() {
+    let program = assembler::assemble(
+        "\
+        mov a 3
+        mov b x
+loop:   addx 2
+        mul b 2
+        sub a 1
+        jnz a loop
+        add x b
+",
+    )
+    .unwrap();
+    let costs = Costs {
+        arithmetic: 2,
+        ..Costs::default()
+    };
+    let trace = trace::record(&program, costs, CYCLE_LIMIT).unwrap();
+    let last = trace.last().unwrap();
+    assert_eq!(last.cycle, 1 + 1 + 3 * (2 + 2 + 2 + 1) + 2 + 1);
+    assert_eq!(last.x(), 7 + 8);
+    assert_eq!(last.pc, program.len());
+}
+
+#[test]
+fn can_stop_endless_programs() {
+    let program = assembler::assemble("      mov a 3\nloop: mul a 3\n      jmp loop\n").unwrap();
+    let states: Vec<State> = Cpu::new(&program).take(1000).collect();
+    assert_eq!(states.len(), 1000);
+    assert!(trace::record(&program, Costs::default(), 1000).is_err());
+    let free = Costs {
+        jump: 0,
+        ..Costs::default()
+    };
+    assert!(Cpu::new(&program).with_costs(free).is_err());
+}
+
 fn solve(instructions: &[Instruction], clocks: &[u64]) -> i64 {
-    let times: BTreeMap<u64, Option<i64>> = clocks.into_iter().map(|c| (*c, None)).collect();
-    let times = Rc::new(RefCell::new(times));
-    let times_clone = times.clone();
-    let mut cpu = Cpu::new(Box::new(move |cpu| {
-        match times_clone.borrow_mut().get_mut(&cpu.cycle) {
-            Some(value) => *value = Some(cpu.cycle as i64 * cpu.x),
-            _ => {}
-        }
-    }));
-    for instruction in instructions {
-        instruction.apply(&mut cpu);
-    }
-    cpu.tick();
-    let times = times.borrow();
-    times.values().filter_map(|v| *v).sum()
+    let clocks: BTreeSet<u64> = clocks.iter().copied().collect();
+    Cpu::new(instructions)
+        .filter(|state| clocks.contains(&state.cycle))
+        .map(|state| (state.cycle as i64).wrapping_mul(state.x()))
+        .fold(0, i64::wrapping_add)
 }
 
 #[test]
 fn can_solve() {
     let data = include_str!("../example.txt");
-    let instructions = parse(&data);
+    let instructions = parse(data);
     let solution = solve(&instructions, TIMES);
     assert_eq!(solution, 13140);
 }
 
+#[test]
+fn can_run_with_extreme_registers() {
+    let instructions = parse("mov x -9223372036854775808\nnoop\nnoop\n");
+    // 2 * i64::MIN wraps to 0, and 3 * i64::MIN back to i64::MIN
+    assert_eq!(solve(&instructions, &[2, 3]), i64::MIN);
+    // only the first pixel is drawn before the mov lands
+    let mut crt = Crt::default();
+    crt.pixels[0][0] = true;
+    assert_eq!(solve_two(&instructions), crt);
+    let mut output = vec![];
+    vcd::write_vcd(&instructions, Costs::default(), 10, &mut output).unwrap();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Crt {
+    pixels: [[bool; WIDTH]; HEIGHT],
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Crt {
+            pixels: [[false; WIDTH]; HEIGHT],
+        }
+    }
+}
+
+impl Crt {
+    pub fn beam(cycle: u64, sprite: i64) -> Option<(usize, usize, bool)> {
+        let position = (cycle as usize).wrapping_sub(1);
+        let (row, column) = (position / WIDTH, position % WIDTH);
+        (row < HEIGHT).then_some((row, column, sprite.abs_diff(column as i64) <= 1))
+    }
+
+    pub fn draw(&mut self, cycle: u64, sprite: i64) {
+        if let Some((row, column, lit)) = Crt::beam(cycle, sprite) {
+            self.pixels[row][column] = lit;
+        }
+    }
+}
+
+impl std::fmt::Display for Crt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.pixels {
+            for pixel in row {
+                write!(f, "{}", if *pixel { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Crt {
+    type Err = String;
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut crt = Crt::default();
+        let lines: Vec<&str> = data.lines().filter(|line| !line.is_empty()).collect();
+        if lines.len() != HEIGHT {
+            return Err(format!("expected {HEIGHT} rows, got {}", lines.len()));
+        }
+        for (row, line) in lines.into_iter().enumerate() {
+            if line.chars().count() != WIDTH {
+                return Err(format!("expected {WIDTH} columns in row {row}"));
+            }
+            for (column, pixel) in line.chars().enumerate() {
+                crt.pixels[row][column] = match pixel {
+                    '#' => true,
+                    '.' => false,
+                    other => return Err(format!("invalid pixel {other:?} in row {row}")),
+                };
+            }
+        }
+        Ok(crt)
+    }
+}
+
+fn solve_two(instructions: &[Instruction]) -> Crt {
+    Cpu::new(instructions).fold(Crt::default(), |mut crt, state| {
+        crt.draw(state.cycle, state.x());
+        crt
+    })
+}
+
+#[test]
+fn can_solve_two() {
+    let data = include_str!("../example.txt");
+    let instructions = parse(data);
+    let crt = solve_two(&instructions);
+    assert_eq!(
+        crt.to_string(),
+        "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....
+"
+    );
+}
+
+#[test]
+fn can_compose_on_one_run() {
+    let instructions = parse(include_str!("../example.txt"));
+    let mut signal = 0;
+    let mut crt = Crt::default();
+    let mut trace = vec![];
+    for state in Cpu::new(&instructions) {
+        if TIMES.contains(&state.cycle) {
+            signal += state.cycle as i64 * state.x();
+        }
+        crt.draw(state.cycle, state.x());
+        trace.push(state);
+    }
+    assert_eq!(signal, solve(&instructions, TIMES));
+    assert_eq!(crt, solve_two(&instructions));
+    let recorded = trace::record(&instructions, Costs::default(), CYCLE_LIMIT);
+    assert_eq!(recorded, Ok(trace));
+}
+
 fn main() {
-    let file = std::env::args().nth(1).unwrap();
-    let data = std::fs::read_to_string(file).unwrap();
+    let args: Vec<String> = std::env::args().collect();
+    match args[1].as_str() {
+        "debug" => {
+            let data = std::fs::read_to_string(&args[2]).unwrap();
+            let data = assembler::assemble(&data).unwrap_or_else(|e| panic!("{e}"));
+            let mut debugger = trace::Debugger::new(&data, Costs::default(), CYCLE_LIMIT)
+                .unwrap_or_else(|e| panic!("{e}"));
+            let stdin = std::io::stdin();
+            trace::repl(&mut debugger, &data, stdin.lock(), std::io::stdout()).unwrap();
+            return;
+        }
+        "trace" => {
+            let data = std::fs::read_to_string(&args[2]).unwrap();
+            let data = assembler::assemble(&data).unwrap_or_else(|e| panic!("{e}"));
+            let output = std::fs::File::create(&args[3]).unwrap();
+            let trace = trace::record(&data, Costs::default(), CYCLE_LIMIT);
+            let trace = trace.unwrap_or_else(|e| panic!("{e}"));
+            trace::write_csv(&trace, output).unwrap();
+            return;
+        }
+        "vcd" => {
+            let data = std::fs::read_to_string(&args[2]).unwrap();
+            let data = assembler::assemble(&data).unwrap_or_else(|e| panic!("{e}"));
+            let output = std::fs::File::create(&args[3]).unwrap();
+            let output = std::io::BufWriter::new(output);
+            vcd::write_vcd(&data, Costs::default(), CYCLE_LIMIT, output).unwrap();
+            return;
+        }
+        "synth" => {
+            let data = std::fs::read_to_string(&args[2]).unwrap();
+            let target: Crt = data.parse().unwrap_or_else(|e| panic!("{e}"));
+            match synth::synthesize(&target) {
+                Ok(program) => program.iter().for_each(|i| println!("{i}")),
+                Err(error) => eprintln!("{error}"),
+            }
+            return;
+        }
+        _ => {}
+    }
+    let data = std::fs::read_to_string(&args[1]).unwrap();
     let data = parse(&data);
     let result = solve(&data, TIMES);
     println!("{result}");
+    let result = solve_two(&data);
+    print!("{result}");
+    match ocr::recognize(&result.to_string()) {
+        Ok(letters) => println!("{letters}"),
+        Err(error) => eprintln!("{error}"),
+    }
 }
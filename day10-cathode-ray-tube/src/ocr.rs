@@ -0,0 +1,279 @@
+use std::fmt;
+
+struct Font {
+    width: usize,
+    height: usize,
+    spacing: usize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+const SMALL: Font = Font {
+    width: 4,
+    height: 6,
+    spacing: 1,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &[".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+const LARGE: Font = Font {
+    width: 6,
+    height: 10,
+    spacing: 2,
+    glyphs: &[
+        (
+            'A',
+            &[
+                "..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'B',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#",
+                "#....#", "#####.",
+            ],
+        ),
+        (
+            'C',
+            &[
+                ".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+                "#....#", ".####.",
+            ],
+        ),
+        (
+            'E',
+            &[
+                "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+        (
+            'F',
+            &[
+                "######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "#.....",
+            ],
+        ),
+        (
+            'G',
+            &[
+                ".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#",
+                "#...##", ".###.#",
+            ],
+        ),
+        (
+            'H',
+            &[
+                "#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'J',
+            &[
+                "...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.",
+                "#...#.", ".###..",
+            ],
+        ),
+        (
+            'K',
+            &[
+                "#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..",
+                "#...#.", "#....#",
+            ],
+        ),
+        (
+            'L',
+            &[
+                "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+        (
+            'N',
+            &[
+                "#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##",
+                "#...##", "#....#",
+            ],
+        ),
+        (
+            'P',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....",
+                "#.....", "#.....",
+            ],
+        ),
+        (
+            'R',
+            &[
+                "#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'X',
+            &[
+                "#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.",
+                "#....#", "#....#",
+            ],
+        ),
+        (
+            'Z',
+            &[
+                "######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....",
+                "#.....", "######",
+            ],
+        ),
+    ],
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OcrError {
+    UnsupportedHeight(usize),
+    InvalidPixel(char),
+    UnknownGlyph { index: usize, bitmap: String },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::UnsupportedHeight(height) => {
+                write!(f, "no font with a height of {height} rows")
+            }
+            OcrError::InvalidPixel(pixel) => write!(f, "invalid pixel {pixel:?}"),
+            OcrError::UnknownGlyph { index, bitmap } => {
+                writeln!(f, "unknown glyph at position {index}:")?;
+                write!(f, "{bitmap}")
+            }
+        }
+    }
+}
+
+fn font(height: usize) -> Result<&'static Font, OcrError> {
+    [&SMALL, &LARGE]
+        .into_iter()
+        .find(|font| font.height == height)
+        .ok_or(OcrError::UnsupportedHeight(height))
+}
+
+pub fn recognize_pixels<R: AsRef<[bool]>>(rows: &[R]) -> Result<String, OcrError> {
+    let font = font(rows.len())?;
+    let columns = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+    let pixel = |row: &R, column: usize| row.as_ref().get(column).copied().unwrap_or(false);
+    let pitch = font.width + font.spacing;
+    let mut result = String::new();
+    for (index, offset) in (0..columns).step_by(pitch).enumerate() {
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (offset..offset + font.width)
+                    .map(|column| if pixel(row, column) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        if glyph.iter().all(|line| !line.contains('#')) {
+            continue;
+        }
+        let letter = font
+            .glyphs
+            .iter()
+            .find(|(_, bitmap)| bitmap.iter().eq(glyph.iter()))
+            .map(|(letter, _)| *letter)
+            .ok_or_else(|| OcrError::UnknownGlyph {
+                index,
+                bitmap: glyph.iter().map(|line| format!("{line}\n")).collect(),
+            })?;
+        result.push(letter);
+    }
+    Ok(result)
+}
+
+pub fn recognize(text: &str) -> Result<String, OcrError> {
+    let rows = text
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.chars()
+                .map(|c| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    other => Err(OcrError::InvalidPixel(other)),
+                })
+                .collect()
+        })
+        .collect::<Result<Vec<Vec<bool>>, _>>()?;
+    recognize_pixels(&rows)
+}
+
+#[cfg(test)]
+fn render(font: &Font, text: &str) -> String {
+    (0..font.height)
+        .map(|row| {
+            let line: Vec<&str> = text
+                .chars()
+                .map(|letter| font.glyphs.iter().find(|(c, _)| *c == letter).unwrap().1[row])
+                .collect();
+            format!("{}\n", line.join(&".".repeat(font.spacing)))
+        })
+        .collect()
+}
+
+#[test]
+fn can_recognize_small() {
+    let text = "\
+###..####.#....###..###..####..##..#..#.
+#..#.#....#....#..#.#..#....#.#..#.#..#.
+#..#.###..#....#..#.###....#..#..#.#..#.
+###..#....#....###..#..#..#...####.#..#.
+#.#..#....#....#.#..#..#.#....#..#.#..#.
+#..#.####.####.#..#.###..####.#..#..##..
+";
+    assert_eq!(recognize(text), Ok("RELRBZAU".into()));
+    let all: String = SMALL.glyphs.iter().map(|(letter, _)| *letter).collect();
+    assert_eq!(recognize(&render(&SMALL, &all)), Ok(all));
+    assert_eq!(recognize(&render(&SMALL, "FIJI")), Ok("FIJI".into()));
+}
+
+#[test]
+fn can_recognize_large() {
+    let all: String = LARGE.glyphs.iter().map(|(letter, _)| *letter).collect();
+    assert_eq!(recognize(&render(&LARGE, &all)), Ok(all));
+}
+
+#[test]
+fn can_report_unknown_glyph() {
+    let text = "\
+####.#..#
+#....#..#
+###..#..#
+#....####
+#.......#
+####....#
+";
+    assert_eq!(
+        recognize(text),
+        Err(OcrError::UnknownGlyph {
+            index: 1,
+            bitmap: "#..#\n#..#\n#..#\n####\n...#\n...#\n".into(),
+        })
+    );
+    assert_eq!(recognize("#\n#\n"), Err(OcrError::UnsupportedHeight(2)));
+}
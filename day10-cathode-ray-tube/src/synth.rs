@@ -0,0 +1,145 @@
+use super::{solve_two, Crt, Instruction, HEIGHT, WIDTH};
+
+// sprite positions outside of this range never light a pixel, so they are
+// all equivalent to one of the two bounds.
+const X_MIN: i64 = -2;
+const X_MAX: i64 = WIDTH as i64 + 1;
+const CYCLES: usize = WIDTH * HEIGHT;
+
+// instruction count, and the pixel index, sprite position and instruction
+// that led to a state.
+type Step = Option<(usize, usize, i64, Instruction)>;
+
+fn matches(target: &Crt, index: usize, x: i64) -> bool {
+    match Crt::beam(index as u64 + 1, x) {
+        Some((row, column, lit)) => target.pixels[row][column] == lit,
+        None => true,
+    }
+}
+
+pub fn synthesize(target: &Crt) -> Result<Vec<Instruction>, String> {
+    let positions = (X_MAX - X_MIN + 1) as usize;
+    let slot = |x: i64| (x - X_MIN) as usize;
+
+    // best[index][x] is the shortest program that has drawn the first
+    // `index` pixels and leaves the sprite at `x`.
+    let mut best: Vec<Vec<Step>> = vec![vec![None; positions]; CYCLES + 2];
+    best[0][slot(1)] = Some((0, 0, 1, Instruction::NoOp));
+
+    for index in 0..CYCLES {
+        for x in X_MIN..=X_MAX {
+            let Some((length, _, _, _)) = best[index][slot(x)] else {
+                continue;
+            };
+            if !matches(target, index, x) {
+                continue;
+            }
+            let mut relax = |next: usize, next_x: i64, instruction| {
+                let entry = &mut best[next][slot(next_x)];
+                if entry.map(|(l, _, _, _)| length + 1 < l).unwrap_or(true) {
+                    *entry = Some((length + 1, index, x, instruction));
+                }
+            };
+            relax(index + 1, x, Instruction::NoOp);
+            if matches(target, index + 1, x) {
+                for next_x in X_MIN..=X_MAX {
+                    relax(index + 2, next_x, Instruction::AddX(next_x - x));
+                }
+            }
+        }
+    }
+
+    // the cpu ticks once more after the program ends, drawing one more
+    // pixel with the final sprite position. the pixels after that stay dark,
+    // so the program can stop early when the rest of the image is dark.
+    let last_lit = (0..CYCLES)
+        .rev()
+        .find(|index| target.pixels[index / WIDTH][index % WIDTH]);
+    let finish = (0..CYCLES + 2)
+        .flat_map(|index| (X_MIN..=X_MAX).map(move |x| (index, x)))
+        .filter(|(index, x)| {
+            *index >= CYCLES
+                || (matches(target, *index, *x) && last_lit.map(|l| l <= *index).unwrap_or(true))
+        })
+        .filter_map(|(index, x)| best[index][slot(x)].map(|entry| (entry.0, index, x)))
+        .min();
+    let Some((_, mut index, mut x)) = finish else {
+        return Err("image cannot be drawn by any program".to_string());
+    };
+
+    let mut program = vec![];
+    while index > 0 {
+        let (_, previous, previous_x, instruction) = best[index][slot(x)].unwrap();
+        program.push(instruction);
+        index = previous;
+        x = previous_x;
+    }
+    program.reverse();
+
+    if solve_two(&program) != *target {
+        return Err("synthesized program does not reproduce the image".to_string());
+    }
+    Ok(program)
+}
+
+#[test]
+fn can_synthesize_example() {
+    let instructions = super::parse(include_str!("../example.txt"));
+    let target = solve_two(&instructions);
+    let program = synthesize(&target).unwrap();
+    assert_eq!(solve_two(&program), target);
+    assert!(program.len() <= instructions.len());
+}
+
+#[test]
+fn can_synthesize_letters() {
+    let target: Crt = "\
+###..####.#....###..###..####..##..#..#.
+#..#.#....#....#..#.#..#....#.#..#.#..#.
+#..#.###..#....#..#.###....#..#..#.#..#.
+###..#....#....###..#..#..#...####.#..#.
+#.#..#....#....#.#..#..#.#....#..#.#..#.
+#..#.####.####.#..#.###..####.#..#..##..
+"
+    .parse()
+    .unwrap();
+    let program = synthesize(&target).unwrap();
+    let image = solve_two(&program).to_string();
+    assert_eq!(super::ocr::recognize(&image), Ok("RELRBZAU".into()));
+}
+
+#[test]
+fn can_detect_unreachable() {
+    // the sprite starts at x = 1, so the first pixel is always lit.
+    let target = Crt::default();
+    assert!(synthesize(&target).is_err());
+
+    // the first two pixels are always drawn with the same sprite position.
+    let mut target = Crt::default();
+    target.pixels[0][0] = true;
+    target.pixels[0][5] = true;
+    assert!(synthesize(&target).is_err());
+}
+
+#[test]
+fn can_stop_early() {
+    // the empty program still ticks once, lighting the first pixel
+    let mut target = Crt::default();
+    target.pixels[0][0] = true;
+    assert_eq!(synthesize(&target), Ok(vec![]));
+
+    let target = solve_two(&[Instruction::NoOp]);
+    assert_eq!(synthesize(&target), Ok(vec![Instruction::NoOp]));
+
+    // lights pixels 3 to 5 with the sprite at 4, then stops
+    let instructions = [
+        Instruction::AddX(3),
+        Instruction::NoOp,
+        Instruction::NoOp,
+        Instruction::NoOp,
+    ];
+    let target = solve_two(&instructions);
+    let program = synthesize(&target).unwrap();
+    assert_eq!(solve_two(&program), target);
+    assert_eq!(program.len(), 3);
+}
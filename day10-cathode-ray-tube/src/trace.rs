@@ -0,0 +1,420 @@
+use super::{Costs, Cpu, Instruction, Register, State, REGISTERS};
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+// fails for programs that are still running after `limit` cycles
+pub fn record(
+    instructions: &[Instruction],
+    costs: Costs,
+    limit: u64,
+) -> Result<Vec<State>, String> {
+    let mut cpu = Cpu::new(instructions).with_costs(costs)?;
+    let trace: Vec<State> = cpu.by_ref().take(limit as usize).collect();
+    match cpu.next() {
+        Some(_) => Err(format!("program did not finish within {limit} cycles")),
+        None => Ok(trace),
+    }
+}
+
+#[test]
+fn can_record() {
+    let instructions = [
+        Instruction::NoOp,
+        Instruction::AddX(3),
+        Instruction::AddX(-5),
+    ];
+    let trace = record(&instructions, Costs::default(), 6).unwrap();
+    let states: Vec<(u64, usize, i64)> = trace.iter().map(|s| (s.cycle, s.pc, s.x())).collect();
+    assert_eq!(
+        states,
+        vec![
+            (1, 0, 1),
+            (2, 1, 1),
+            (3, 1, 1),
+            (4, 2, 4),
+            (5, 2, 4),
+            (6, 3, -1)
+        ]
+    );
+    assert!(record(&instructions, Costs::default(), 5).is_err());
+}
+
+pub fn write_csv<W: Write>(trace: &[State], mut writer: W) -> io::Result<()> {
+    write!(writer, "cycle,instruction")?;
+    for register in REGISTERS {
+        write!(writer, ",{register}")?;
+    }
+    writeln!(writer)?;
+    for state in trace {
+        write!(writer, "{},{}", state.cycle, state.pc)?;
+        for value in state.registers {
+            write!(writer, ",{value}")?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn can_write_csv() {
+    let instructions = [
+        Instruction::NoOp,
+        Instruction::AddX(3),
+        "mov c -2".parse().unwrap(),
+    ];
+    let trace = record(&instructions, Costs::default(), 10).unwrap();
+    let mut output = Vec::new();
+    write_csv(&trace, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\
+cycle,instruction,x,a,b,c,d
+1,0,1,0,0,0,0
+2,1,1,0,0,0,0
+3,1,1,0,0,0,0
+4,2,4,0,0,0,0
+5,3,4,0,0,-2,0
+"
+    );
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl Comparison {
+    fn compare(&self, left: i64, right: i64) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    Cycle(u64),
+    Register(Register, Comparison, i64),
+}
+
+impl Breakpoint {
+    pub fn matches(&self, state: &State) -> bool {
+        match self {
+            Breakpoint::Cycle(cycle) => state.cycle == *cycle,
+            Breakpoint::Register(register, comparison, value) => {
+                comparison.compare(state.register(*register), *value)
+            }
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+        if input.starts_with(|c: char| c.is_ascii_digit()) {
+            return input
+                .parse()
+                .map(Breakpoint::Cycle)
+                .map_err(|e| format!("error parsing cycle: {e}"));
+        }
+        let split = input
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(input.len());
+        let (register, condition) = input.split_at(split);
+        let register = register.parse()?;
+        let comparisons = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessEqual),
+            (">=", Comparison::GreaterEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        let (value, comparison) = comparisons
+            .iter()
+            .find_map(|(op, comparison)| condition.strip_prefix(op).map(|v| (v, *comparison)))
+            .ok_or_else(|| format!("invalid condition {condition}"))?;
+        let value = value
+            .parse()
+            .map_err(|e| format!("error parsing register value: {e}"))?;
+        Ok(Breakpoint::Register(register, comparison, value))
+    }
+}
+
+#[test]
+fn can_parse_breakpoint() {
+    assert_eq!("20".parse(), Ok(Breakpoint::Cycle(20)));
+    assert_eq!(
+        "x == -3".parse(),
+        Ok(Breakpoint::Register(Register::X, Comparison::Equal, -3))
+    );
+    assert_eq!(
+        "x>=10".parse(),
+        Ok(Breakpoint::Register(
+            Register::X,
+            Comparison::GreaterEqual,
+            10
+        ))
+    );
+    assert_eq!(
+        "c<0".parse(),
+        Ok(Breakpoint::Register(Register::C, Comparison::Less, 0))
+    );
+    assert!("x~1".parse::<Breakpoint>().is_err());
+    assert!("y==1".parse::<Breakpoint>().is_err());
+    assert!("abc".parse::<Breakpoint>().is_err());
+}
+
+// runs the cpu one cycle at a time, so programs that never halt can still
+// be stepped through up to the cycle limit.
+pub struct Debugger<'a> {
+    cpu: Cpu<'a>,
+    limit: u64,
+    trace: Vec<State>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(instructions: &'a [Instruction], costs: Costs, limit: u64) -> Result<Self, String> {
+        Ok(Debugger {
+            cpu: Cpu::new(instructions).with_costs(costs)?,
+            limit,
+            trace: vec![],
+            breakpoints: vec![],
+        })
+    }
+
+    // the cycles stepped through so far
+    pub fn trace(&self) -> &[State] {
+        &self.trace
+    }
+
+    pub fn current(&self) -> Option<&State> {
+        self.trace.last()
+    }
+
+    pub fn at_limit(&self) -> bool {
+        self.trace.len() as u64 >= self.limit
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        (index < self.breakpoints.len()).then(|| self.breakpoints.remove(index))
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn step(&mut self) -> Option<&State> {
+        if self.at_limit() {
+            return None;
+        }
+        let state = self.cpu.next()?;
+        self.trace.push(state);
+        self.current()
+    }
+
+    pub fn resume(&mut self) -> Option<(State, usize)> {
+        while let Some(state) = self.step().copied() {
+            if let Some(index) = self.breakpoints.iter().position(|b| b.matches(&state)) {
+                return Some((state, index));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn can_debug() {
+    let instructions = parse_example();
+    let mut debugger = Debugger::new(&instructions, Costs::default(), 1000).unwrap();
+    assert_eq!(debugger.current(), None);
+    assert_eq!(debugger.step().map(|s| s.cycle), Some(1));
+    debugger.add_breakpoint(Breakpoint::Cycle(20));
+    debugger.add_breakpoint(Breakpoint::Register(Register::X, Comparison::Greater, 30));
+    let (state, index) = debugger.resume().unwrap();
+    assert_eq!((state.cycle, state.x(), index), (20, 21, 0));
+    assert_eq!(debugger.remove_breakpoint(0), Some(Breakpoint::Cycle(20)));
+    let (state, index) = debugger.resume().unwrap();
+    assert_eq!((state.x(), index), (33, 0));
+    debugger.remove_breakpoint(0);
+    assert_eq!(debugger.resume(), None);
+    assert_eq!(debugger.current().map(|s| s.cycle), Some(241));
+    assert!(!debugger.at_limit());
+}
+
+#[test]
+fn can_debug_endless_programs() {
+    let program = super::assembler::assemble("loop: add a 1\n      jmp loop\n").unwrap();
+    let mut debugger = Debugger::new(&program, Costs::default(), 100).unwrap();
+    debugger.add_breakpoint("a==10".parse().unwrap());
+    let (state, _) = debugger.resume().unwrap();
+    assert_eq!((state.cycle, state.register(Register::A)), (20, 10));
+    debugger.remove_breakpoint(0);
+    assert_eq!(debugger.resume(), None);
+    assert!(debugger.at_limit());
+    assert_eq!(debugger.trace().len(), 100);
+}
+
+#[cfg(test)]
+fn parse_example() -> Vec<Instruction> {
+    super::parse(include_str!("../example.txt"))
+}
+
+fn describe(state: &State, instructions: &[Instruction]) -> String {
+    match instructions.get(state.pc) {
+        Some(instruction) => format!(
+            "cycle {} pc {} x {} ({instruction})",
+            state.cycle,
+            state.pc,
+            state.x()
+        ),
+        None => format!(
+            "cycle {} pc {} x {} (halted)",
+            state.cycle,
+            state.pc,
+            state.x()
+        ),
+    }
+}
+
+fn stopped(debugger: &Debugger) -> String {
+    match debugger.at_limit() {
+        true => format!("stopped at the limit of {} cycles", debugger.trace().len()),
+        false => "program finished".to_string(),
+    }
+}
+
+const HELP: &str = "\
+commands:
+  step [n]        advance n cycles (default 1)
+  continue        run until the next breakpoint
+  break <cond>    break on a cycle number or a condition like x==3, a<0
+  delete <n>      remove breakpoint n
+  breakpoints     list breakpoints
+  print           show the current state
+  export <file>   write the cycles so far as CSV
+  quit            leave the debugger";
+
+pub fn repl<R: BufRead, W: Write>(
+    debugger: &mut Debugger,
+    instructions: &[Instruction],
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.trim().splitn(2, ' ');
+        let argument = words.clone().nth(1).unwrap_or("").trim();
+        match words.next().unwrap_or("") {
+            "" => {}
+            "s" | "step" => {
+                let count = if argument.is_empty() {
+                    Ok(1)
+                } else {
+                    argument.parse::<usize>()
+                };
+                match count {
+                    Ok(count) => {
+                        for _ in 0..count {
+                            if debugger.step().is_none() {
+                                writeln!(output, "{}", stopped(debugger))?;
+                                break;
+                            }
+                        }
+                        if let Some(state) = debugger.current() {
+                            writeln!(output, "{}", describe(state, instructions))?;
+                        }
+                    }
+                    Err(e) => writeln!(output, "error parsing step count: {e}")?,
+                }
+            }
+            "c" | "continue" => match debugger.resume() {
+                Some((state, index)) => {
+                    writeln!(output, "breakpoint {index} hit")?;
+                    writeln!(output, "{}", describe(&state, instructions))?;
+                }
+                None => writeln!(output, "{}", stopped(debugger))?,
+            },
+            "b" | "break" => match argument.parse() {
+                Ok(breakpoint) => {
+                    debugger.add_breakpoint(breakpoint);
+                    let index = debugger.breakpoints().len() - 1;
+                    writeln!(output, "breakpoint {index} set")?;
+                }
+                Err(e) => writeln!(output, "{e}")?,
+            },
+            "d" | "delete" => match argument
+                .parse()
+                .ok()
+                .and_then(|i| debugger.remove_breakpoint(i))
+            {
+                Some(_) => writeln!(output, "breakpoint {argument} deleted")?,
+                None => writeln!(output, "no breakpoint {argument}")?,
+            },
+            "breakpoints" => {
+                for (index, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                    writeln!(output, "{index}: {breakpoint:?}")?;
+                }
+            }
+            "p" | "print" => match debugger.current() {
+                Some(state) => writeln!(output, "{}", describe(state, instructions))?,
+                None => writeln!(output, "program not started")?,
+            },
+            "export" => match std::fs::File::create(argument) {
+                Ok(file) => {
+                    write_csv(debugger.trace(), file)?;
+                    writeln!(
+                        output,
+                        "wrote {} cycles to {argument}",
+                        debugger.trace().len()
+                    )?;
+                }
+                Err(e) => writeln!(output, "error creating {argument}: {e}")?,
+            },
+            "q" | "quit" => return Ok(()),
+            "h" | "help" => writeln!(output, "{HELP}")?,
+            other => writeln!(output, "unknown command {other}, try help")?,
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn can_run_repl() {
+    let instructions = parse_example();
+    let mut debugger = Debugger::new(&instructions, Costs::default(), 1000).unwrap();
+    let input = "step 3\nbreak x==21\ncontinue\nprint\nfoo\nquit\nstep\n";
+    let mut output = Vec::new();
+    repl(&mut debugger, &instructions, input.as_bytes(), &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\
+> cycle 3 pc 1 x 16 (addx -11)
+> breakpoint 0 set
+> breakpoint 0 hit
+cycle 19 pc 9 x 21 (noop)
+> cycle 19 pc 9 x 21 (noop)
+> unknown command foo, try help
+> "
+    );
+}
@@ -0,0 +1,173 @@
+use super::{trace, Costs, Crt, Instruction, State};
+use std::io::{self, Write};
+
+const SIGNALS: &[(&str, &str, u32, &str)] = &[
+    ("wire", "!", 1, "clk"),
+    ("integer", "\"", 64, "x"),
+    ("integer", "#", 32, "pc"),
+    ("string", "$", 1, "instruction"),
+    ("integer", "%", 32, "beam"),
+    ("wire", "&", 1, "pixel"),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Sample {
+    x: String,
+    pc: String,
+    instruction: String,
+    beam: String,
+    pixel: String,
+}
+
+fn sample(state: &State, instructions: &[Instruction]) -> Sample {
+    let instruction = match instructions.get(state.pc) {
+        Some(instruction) => instruction.to_string().replace(' ', "_"),
+        None => "halt".to_string(),
+    };
+    let (beam, pixel) = match Crt::beam(state.cycle, state.x()) {
+        Some((row, column, lit)) => (
+            format!("b{:b}", row * super::WIDTH + column),
+            if lit { "1" } else { "0" }.to_string(),
+        ),
+        None => ("bx".to_string(), "x".to_string()),
+    };
+    Sample {
+        x: format!("b{:b}", state.x() as u64),
+        pc: format!("b{:b}", state.pc),
+        instruction: format!("s{instruction}"),
+        beam,
+        pixel,
+    }
+}
+
+fn record(instructions: &[Instruction], costs: Costs, limit: u64) -> io::Result<Vec<Sample>> {
+    let trace = trace::record(instructions, costs, limit)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    Ok(trace
+        .iter()
+        .map(|state| sample(state, instructions))
+        .collect())
+}
+
+fn write_value<W: Write>(writer: &mut W, value: &str, id: &str) -> io::Result<()> {
+    if value.len() == 1 {
+        writeln!(writer, "{value}{id}")
+    } else {
+        writeln!(writer, "{value} {id}")
+    }
+}
+
+pub fn write_vcd<W: Write>(
+    instructions: &[Instruction],
+    costs: Costs,
+    limit: u64,
+    mut writer: W,
+) -> io::Result<()> {
+    let samples = record(instructions, costs, limit)?;
+    writeln!(writer, "$version day10-cathode-ray-tube $end")?;
+    writeln!(writer, "$timescale 1ns $end")?;
+    writeln!(writer, "$scope module cpu $end")?;
+    for (kind, id, width, name) in SIGNALS {
+        writeln!(writer, "$var {kind} {width} {id} {name} $end")?;
+    }
+    writeln!(writer, "$upscope $end")?;
+    writeln!(writer, "$enddefinitions $end")?;
+
+    let mut previous: Option<Sample> = None;
+    for (index, sample) in samples.iter().enumerate() {
+        writeln!(writer, "#{}", index * 2)?;
+        if previous.is_none() {
+            writeln!(writer, "$dumpvars")?;
+        }
+        writeln!(writer, "1!")?;
+        let values = [
+            (&sample.x, "\""),
+            (&sample.pc, "#"),
+            (&sample.instruction, "$"),
+            (&sample.beam, "%"),
+            (&sample.pixel, "&"),
+        ];
+        let old = previous
+            .as_ref()
+            .map(|p| [&p.x, &p.pc, &p.instruction, &p.beam, &p.pixel]);
+        for (position, (value, id)) in values.iter().enumerate() {
+            if old.map(|old| old[position] != *value).unwrap_or(true) {
+                write_value(&mut writer, value, id)?;
+            }
+        }
+        if previous.is_none() {
+            writeln!(writer, "$end")?;
+        }
+        writeln!(writer, "#{}", index * 2 + 1)?;
+        writeln!(writer, "0!")?;
+        previous = Some(sample.clone());
+    }
+    writeln!(writer, "#{}", samples.len() * 2)?;
+    Ok(())
+}
+
+#[test]
+fn can_write_vcd() {
+    let instructions = [Instruction::AddX(3), Instruction::NoOp];
+    let mut output = Vec::new();
+    write_vcd(&instructions, Costs::default(), 10, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "\
+$version day10-cathode-ray-tube $end
+$timescale 1ns $end
+$scope module cpu $end
+$var wire 1 ! clk $end
+$var integer 64 \" x $end
+$var integer 32 # pc $end
+$var string 1 $ instruction $end
+$var integer 32 % beam $end
+$var wire 1 & pixel $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+1!
+b1 \"
+b0 #
+saddx_3 $
+b0 %
+1&
+$end
+#1
+0!
+#2
+1!
+b1 %
+#3
+0!
+#4
+1!
+b100 \"
+b1 #
+snoop $
+b10 %
+0&
+#5
+0!
+#6
+1!
+b10 #
+shalt $
+b11 %
+1&
+#7
+0!
+#8
+"
+    );
+}
+
+#[test]
+fn can_refuse_endless_programs() {
+    let instructions = [Instruction::Jump(super::Condition::Always, 0)];
+    let mut output = Vec::new();
+    let error = write_vcd(&instructions, Costs::default(), 10, &mut output).unwrap_err();
+    assert_eq!(error.to_string(), "program did not finish within 10 cycles");
+    assert!(output.is_empty());
+}
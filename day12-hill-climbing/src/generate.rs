@@ -0,0 +1,187 @@
+use super::{Grid, Square};
+
+// the route has to climb from `a` to `x` before its last two steps (`y`, `E`)
+const CLIMB: usize = 23;
+// `z` walls can only be entered from `y` or higher, which the route only
+// reaches right next to the end.
+const WALL: Square = Square::Height(25);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terrain {
+    pub width: usize,
+    pub height: usize,
+    pub length: Option<usize>,
+    pub starts: usize,
+    pub seed: u64,
+}
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // xorshift64*
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+// snakes through every other row, with a single connecting square between
+// two rows, so that squares of the route only touch their neighbours on it.
+fn route(width: usize, height: usize) -> Vec<(usize, usize)> {
+    let mut route = vec![];
+    for row in (0..height).step_by(2) {
+        let columns: Vec<usize> = if (row / 2) % 2 == 0 {
+            (0..width).collect()
+        } else {
+            (0..width).rev().collect()
+        };
+        route.extend(columns.iter().map(|column| (row, *column)));
+        if row + 2 < height {
+            route.push((row + 1, *columns.last().unwrap()));
+        }
+    }
+    route
+}
+
+pub fn generate(terrain: &Terrain) -> Result<Grid, String> {
+    let mut rng = Rng(terrain.seed.max(1));
+    let route = route(terrain.width, terrain.height);
+    let shortest = CLIMB + 2 + terrain.starts;
+    let longest = route.len().saturating_sub(1);
+    if shortest > longest {
+        return Err(format!(
+            "a {}x{} map fits paths of at most {longest} steps, but {shortest} are needed",
+            terrain.width, terrain.height
+        ));
+    }
+    let length = match terrain.length {
+        Some(length) if length < shortest || length > longest => {
+            return Err(format!(
+                "path length {length} is outside of the possible range {shortest}..={longest}"
+            ));
+        }
+        Some(length) => length,
+        None => shortest + rng.below(longest - shortest + 1),
+    };
+
+    // after the start and the `a` squares the route steps up to `b`, and
+    // then has to spread the remaining increments over the free steps.
+    let free = length - terrain.starts - 3;
+    let mut steps: Vec<bool> = (0..free).map(|index| index < CLIMB - 1).collect();
+    for index in (1..steps.len()).rev() {
+        steps.swap(index, rng.below(index + 1));
+    }
+
+    let mut squares = vec![WALL; terrain.width * terrain.height];
+    let mut elevation = 0;
+    let heights = std::iter::repeat_n(false, terrain.starts)
+        .chain(std::iter::once(true))
+        .chain(steps);
+    for ((x, y), step) in route[1..length - 1].iter().zip(heights) {
+        elevation += step as u8;
+        squares[x * terrain.width + y] = Square::Height(elevation);
+    }
+    let (x, y) = route[0];
+    squares[x * terrain.width + y] = Square::Start;
+    let (x, y) = route[length - 1];
+    squares[x * terrain.width + y] = Square::Height(CLIMB as u8 + 1);
+    let (x, y) = route[length];
+    squares[x * terrain.width + y] = Square::End;
+
+    Ok(Grid {
+        width: terrain.width,
+        height: terrain.height,
+        squares,
+    })
+}
+
+#[test]
+fn can_generate() {
+    for seed in 1..20 {
+        let terrain = Terrain {
+            width: 20,
+            height: 15,
+            length: Some(60 + seed as usize),
+            starts: 5,
+            seed,
+        };
+        let grid = generate(&terrain).unwrap();
+        let text = grid.to_string();
+        assert_eq!(super::parse(&text), grid);
+        let field = super::distances_to(&grid, super::find(&grid, Square::End));
+        assert_eq!(super::solve(&grid, &field), 60 + seed);
+        assert_eq!(super::solve_two(&grid, &field), Some(60 + seed - 5));
+        let starts = grid.iter().filter(|(_, s)| **s == Square::Height(0));
+        assert_eq!(starts.count(), 5);
+    }
+}
+
+#[test]
+fn can_solve_default_terrain() {
+    // what `generate 20 10` makes, without any extra `a` squares
+    let terrain = Terrain {
+        width: 20,
+        height: 10,
+        length: None,
+        starts: 0,
+        seed: 1,
+    };
+    let grid = super::parse(&generate(&terrain).unwrap().to_string());
+    let field = super::distances_to(&grid, super::find(&grid, Square::End));
+    assert!(super::solve(&grid, &field) > 0);
+    assert_eq!(super::solve_two(&grid, &field), None);
+}
+
+#[test]
+fn can_generate_random_length() {
+    let terrain = Terrain {
+        width: 40,
+        height: 41,
+        length: None,
+        starts: 0,
+        seed: 7,
+    };
+    let grid = generate(&terrain).unwrap();
+    let field = super::distances_to(&grid, super::find(&grid, Square::End));
+    let length = super::solve(&grid, &field);
+    assert!((25..=40 * 21 + 20).contains(&length));
+}
+
+#[test]
+fn can_reject_impossible_lengths() {
+    let terrain = Terrain {
+        width: 10,
+        height: 5,
+        length: None,
+        starts: 0,
+        seed: 1,
+    };
+    assert!(generate(&terrain).is_ok());
+    let small = Terrain {
+        width: 5,
+        ..terrain.clone()
+    };
+    assert!(generate(&small).is_err());
+    let short = Terrain {
+        length: Some(24),
+        ..terrain.clone()
+    };
+    assert!(generate(&short).is_err());
+    let long = Terrain {
+        length: Some(32),
+        ..terrain.clone()
+    };
+    assert!(generate(&long).is_err());
+    let crowded = Terrain {
+        length: Some(30),
+        starts: 6,
+        ..terrain
+    };
+    assert!(generate(&crowded).is_err());
+}
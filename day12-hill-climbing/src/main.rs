@@ -1,6 +1,12 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+use std::ops::Index;
 use std::str::FromStr;
 
+mod generate;
+mod pnm;
+mod waypoints;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Square {
     Height(u8),
@@ -16,23 +22,30 @@ impl Square {
             Self::End => 25,
         }
     }
-
-    fn can_move(&self, other: &Square) -> bool {
-        (self.elevation() + 1) >= other.elevation()
-    }
 }
 
 #[test]
 fn can_check_move() {
     use Square::*;
-    assert_eq!(Height(5).can_move(&Height(6)), true);
-    assert_eq!(Height(5).can_move(&Height(7)), false);
-    assert_eq!(Height(5).can_move(&Height(5)), true);
-    assert_eq!(Height(5).can_move(&Height(4)), true);
-    assert_eq!(Height(5).can_move(&Height(3)), true);
-    assert_eq!(Height(5).can_move(&Height(2)), true);
-    assert_eq!(Height(5).can_move(&Height(1)), true);
-    assert_eq!(Height(5).can_move(&Height(0)), true);
+    let rules = MovementRules::default();
+    assert!(rules.can_move(&Height(5), &Height(6)));
+    assert!(!rules.can_move(&Height(5), &Height(7)));
+    assert!(rules.can_move(&Height(5), &Height(5)));
+    assert!(rules.can_move(&Height(5), &Height(4)));
+    assert!(rules.can_move(&Height(5), &Height(3)));
+    assert!(rules.can_move(&Height(5), &Height(2)));
+    assert!(rules.can_move(&Height(5), &Height(1)));
+    assert!(rules.can_move(&Height(5), &Height(0)));
+}
+
+impl std::fmt::Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Square::Start => write!(f, "S"),
+            Square::End => write!(f, "E"),
+            Square::Height(height) => write!(f, "{}", (b'a' + height) as char),
+        }
+    }
 }
 
 impl FromStr for Square {
@@ -42,151 +55,685 @@ impl FromStr for Square {
         match single {
             'S' => Ok(Square::Start),
             'E' => Ok(Square::End),
-            'a'..='z' => Ok(Square::Height(single as u8 - 'a' as u8)),
+            'a'..='z' => Ok(Square::Height(single as u8 - b'a')),
             _ => Err("Unknown char"),
         }
     }
 }
 
-fn parse(data: &str) -> BTreeMap<(usize, usize), Square> {
-    data.lines()
-        .enumerate()
-        .map(|(x, line)| {
-            line.split("")
-                .filter(|c| !c.is_empty())
-                .enumerate()
-                .map(move |(y, c)| ((x, y), c.parse().unwrap()))
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Grid {
+    width: usize,
+    height: usize,
+    squares: Vec<Square>,
+}
+
+const DELTAS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+impl Grid {
+    fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.height && y < self.width
+    }
+
+    fn get(&self, position: (usize, usize)) -> Option<&Square> {
+        self.contains(position)
+            .then(|| &self.squares[position.0 * self.width + position.1])
+    }
+
+    fn iter(&self) -> impl Iterator<Item = ((usize, usize), &Square)> {
+        self.squares
+            .iter()
+            .enumerate()
+            .map(|(index, square)| ((index / self.width, index % self.width), square))
+    }
+
+    fn neighbours(
+        &self,
+        (x, y): (usize, usize),
+        diagonal: bool,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let count = if diagonal { 8 } else { 4 };
+        DELTAS[..count].iter().filter_map(move |(dx, dy)| {
+            let position = (x.checked_add_signed(*dx)?, y.checked_add_signed(*dy)?);
+            self.contains(position).then_some(position)
         })
-        .flatten()
-        .collect()
+    }
+}
+
+impl Index<(usize, usize)> for Grid {
+    type Output = Square;
+    fn index(&self, position: (usize, usize)) -> &Square {
+        self.get(position)
+            .unwrap_or_else(|| panic!("position {position:?} out of bounds"))
+    }
+}
+
+impl std::fmt::Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.squares.chunks(self.width) {
+            for square in row {
+                write!(f, "{square}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse(data: &str) -> Grid {
+    let mut grid = Grid {
+        width: 0,
+        height: 0,
+        squares: vec![],
+    };
+    for line in data.lines() {
+        let row: Vec<Square> = line
+            .split("")
+            .filter(|c| !c.is_empty())
+            .map(|c| c.parse().unwrap())
+            .collect();
+        if grid.height == 0 {
+            grid.width = row.len();
+        }
+        assert_eq!(row.len(), grid.width, "ragged row {}", grid.height);
+        grid.squares.extend(row);
+        grid.height += 1;
+    }
+    grid
 }
 
 #[test]
 fn can_parse() {
     let data = parse(include_str!("../example.txt"));
-    assert_eq!(data[&(0, 0)], Square::Start);
-    assert_eq!(data[&(0, 1)], Square::Height(0));
-    assert_eq!(data[&(0, 2)], Square::Height(1));
-    assert_eq!(data[&(4, 0)], Square::Height(0));
-    assert_eq!(data[&(4, 1)], Square::Height(1));
-    assert_eq!(data[&(4, 2)], Square::Height(3));
-    assert_eq!(data[&(4, 3)], Square::Height(4));
-    assert_eq!(data[&(4, 4)], Square::Height(5));
+    assert_eq!((data.height, data.width), (5, 8));
+    assert_eq!(data[(0, 0)], Square::Start);
+    assert_eq!(data[(0, 1)], Square::Height(0));
+    assert_eq!(data[(0, 2)], Square::Height(1));
+    assert_eq!(data[(4, 0)], Square::Height(0));
+    assert_eq!(data[(4, 1)], Square::Height(1));
+    assert_eq!(data[(4, 2)], Square::Height(3));
+    assert_eq!(data[(4, 3)], Square::Height(4));
+    assert_eq!(data[(4, 4)], Square::Height(5));
+    assert_eq!(data.get((5, 0)), None);
+    assert_eq!(data.get((0, 8)), None);
+    assert_eq!(data.to_string(), include_str!("../example.txt"));
 }
 
-fn directions(coord: (usize, usize)) -> Vec<(usize, usize)> {
-    let mut out = vec![];
+#[test]
+fn can_get_neighbours() {
+    let data = parse(&"aaaaaaaa\n".repeat(8));
+    let neighbours = |position| data.neighbours(position, false).collect::<Vec<_>>();
+    assert_eq!(neighbours((5, 5)), vec![(4, 5), (6, 5), (5, 4), (5, 6)]);
+    assert_eq!(neighbours((0, 5)), vec![(1, 5), (0, 4), (0, 6)]);
+    assert_eq!(neighbours((5, 0)), vec![(4, 0), (6, 0), (5, 1)]);
+    assert_eq!(neighbours((0, 0)), vec![(1, 0), (0, 1)]);
+    assert_eq!(neighbours((7, 7)), vec![(6, 7), (7, 6)]);
+    let diagonal = data.neighbours((0, 0), true).collect::<Vec<_>>();
+    assert_eq!(diagonal, vec![(1, 0), (0, 1), (1, 1)]);
+    assert_eq!(data.neighbours((5, 5), true).count(), 8);
+}
 
-    match coord {
-        (0, y) => {
-            out.push((1, y));
-        }
-        (x, y) => {
-            out.push((x - 1, y));
-            out.push((x + 1, y));
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MovementRules {
+    max_climb: u8,
+    max_descent: Option<u8>,
+    diagonal: bool,
+    step_cost: u64,
+    climb_cost: u64,
+    descent_cost: u64,
+}
+
+impl Default for MovementRules {
+    fn default() -> Self {
+        MovementRules {
+            max_climb: 1,
+            max_descent: None,
+            diagonal: false,
+            step_cost: 1,
+            climb_cost: 0,
+            descent_cost: 0,
         }
     }
+}
 
-    match coord {
-        (x, 0) => {
-            out.push((x, 1));
-        }
-        (x, y) => {
-            out.push((x, y - 1));
-            out.push((x, y + 1));
+impl MovementRules {
+    fn can_move(&self, from: &Square, to: &Square) -> bool {
+        let (from, to) = (from.elevation(), to.elevation());
+        if to > from {
+            to - from <= self.max_climb
+        } else {
+            self.max_descent.map(|max| from - to <= max).unwrap_or(true)
         }
     }
 
-    out
+    fn cost(&self, from: &Square, to: &Square) -> u64 {
+        let (from, to) = (from.elevation() as u64, to.elevation() as u64);
+        self.step_cost
+            + to.saturating_sub(from) * self.climb_cost
+            + from.saturating_sub(to) * self.descent_cost
+    }
+
+    fn is_uniform(&self) -> bool {
+        self.climb_cost == 0 && self.descent_cost == 0
+    }
+
+    // lower bound on the cost from one square to another, for A*
+    fn estimate(&self, from: ((usize, usize), &Square), to: ((usize, usize), &Square)) -> u64 {
+        let rows = from.0 .0.abs_diff(to.0 .0) as u64;
+        let columns = from.0 .1.abs_diff(to.0 .1) as u64;
+        let steps = if self.diagonal {
+            rows.max(columns)
+        } else {
+            rows + columns
+        };
+        let (from, to) = (from.1.elevation() as u64, to.1.elevation() as u64);
+        steps * self.step_cost
+            + to.saturating_sub(from) * self.climb_cost
+            + from.saturating_sub(to) * self.descent_cost
+    }
 }
 
 #[test]
-fn can_get_directions() {
-    assert_eq!(directions((5, 5)), vec![(4, 5), (6, 5), (5, 4), (5, 6)]);
-    assert_eq!(directions((0, 5)), vec![(1, 5), (0, 4), (0, 6)]);
-    assert_eq!(directions((5, 0)), vec![(4, 0), (6, 0), (5, 1)]);
-    assert_eq!(directions((0, 0)), vec![(1, 0), (0, 1)]);
+fn can_apply_movement_rules() {
+    use Square::*;
+    let rules = MovementRules {
+        max_climb: 2,
+        max_descent: Some(1),
+        climb_cost: 3,
+        descent_cost: 1,
+        ..MovementRules::default()
+    };
+    assert!(rules.can_move(&Height(5), &Height(7)));
+    assert!(!rules.can_move(&Height(5), &Height(8)));
+    assert!(rules.can_move(&Height(5), &Height(4)));
+    assert!(!rules.can_move(&Height(5), &Height(3)));
+    assert_eq!(rules.cost(&Height(5), &Height(7)), 7);
+    assert_eq!(rules.cost(&Height(5), &Height(4)), 2);
+    assert_eq!(rules.cost(&Height(5), &Height(5)), 1);
+    assert!(!rules.is_uniform());
+    assert!(MovementRules::default().is_uniform());
 }
 
-fn shortest_distance(
-    data: &BTreeMap<(usize, usize), Square>,
-    start: (usize, usize),
-    end: (usize, usize),
-) -> u64 {
-    let mut distances: BTreeMap<(usize, usize), u64> = BTreeMap::new();
-    let mut current = BTreeSet::new();
+#[derive(Clone, Debug)]
+struct DistanceField {
+    width: usize,
+    height: usize,
+    distances: Vec<Option<u64>>,
+    next: Vec<Option<(usize, usize)>>,
+}
 
-    distances.insert(start, 0);
-    current.insert(start);
+impl DistanceField {
+    fn new(grid: &Grid) -> Self {
+        DistanceField {
+            width: grid.width,
+            height: grid.height,
+            distances: vec![None; grid.squares.len()],
+            next: vec![None; grid.squares.len()],
+        }
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> Option<usize> {
+        (x < self.height && y < self.width).then_some(x * self.width + y)
+    }
+
+    fn distance(&self, position: (usize, usize)) -> Option<u64> {
+        self.distances[self.index(position)?]
+    }
 
-    loop {
-        if let Some(length) = distances.get(&end) {
-            return *length;
+    fn set(&mut self, position: (usize, usize), distance: u64, next: Option<(usize, usize)>) {
+        let index = self.index(position).unwrap();
+        self.distances[index] = Some(distance);
+        self.next[index] = next;
+    }
+
+    fn path(&self, start: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        self.distance(start)?;
+        let mut path = vec![start];
+        let mut current = start;
+        while let Some(next) = self.next[self.index(current)?] {
+            path.push(next);
+            current = next;
         }
+        Some(path)
+    }
+}
 
-        assert!(!current.is_empty());
+fn distances_to(data: &Grid, end: (usize, usize)) -> DistanceField {
+    distances_to_with(data, end, &MovementRules::default())
+}
+
+fn distances_to_with(data: &Grid, end: (usize, usize), rules: &MovementRules) -> DistanceField {
+    let mut field = DistanceField::new(data);
+
+    if rules.is_uniform() {
+        let mut queue = VecDeque::new();
+        field.set(end, 0, None);
+        queue.push_back(end);
 
-        for entry in std::mem::take(&mut current) {
-            let square = data.get(&entry).unwrap();
-            let next_distance = distances.get(&entry).unwrap() + 1;
-            let directions = directions(entry)
-                .into_iter()
-                .filter_map(|t| data.get(&t).map(|square| (t, square)));
-            for (next_pos, next_square) in directions {
-                if !square.can_move(next_square) {
+        while let Some(entry_pos) = queue.pop_front() {
+            let square = data[entry_pos];
+            let next_distance = field.distance(entry_pos).unwrap() + rules.step_cost;
+            for previous_pos in data.neighbours(entry_pos, rules.diagonal) {
+                // walking backwards, so check the move from the neighbour
+                if !rules.can_move(&data[previous_pos], &square) {
                     continue;
                 }
 
-                if distances.get(&next_pos) != Some(&next_distance) {
-                    distances.insert(next_pos, next_distance);
-                    current.insert(next_pos);
+                if field.distance(previous_pos).is_none() {
+                    field.set(previous_pos, next_distance, Some(entry_pos));
+                    queue.push_back(previous_pos);
+                }
+            }
+        }
+    } else {
+        let mut queue = BinaryHeap::new();
+        field.set(end, 0, None);
+        queue.push(Reverse((0, end)));
+
+        while let Some(Reverse((distance, entry_pos))) = queue.pop() {
+            if field.distance(entry_pos).unwrap() < distance {
+                continue;
+            }
+            let square = data[entry_pos];
+            for previous_pos in data.neighbours(entry_pos, rules.diagonal) {
+                let previous_square = &data[previous_pos];
+                if !rules.can_move(previous_square, &square) {
+                    continue;
+                }
+
+                let next_distance = distance + rules.cost(previous_square, &square);
+                if field
+                    .distance(previous_pos)
+                    .map(|d| next_distance < d)
+                    .unwrap_or(true)
+                {
+                    field.set(previous_pos, next_distance, Some(entry_pos));
+                    queue.push(Reverse((next_distance, previous_pos)));
                 }
             }
         }
     }
+
+    field
 }
 
-fn solve(data: &BTreeMap<(usize, usize), Square>) -> u64 {
-    let start = *data
-        .iter()
-        .find(|(_, value)| **value == Square::Start)
+#[test]
+fn can_compute_distances() {
+    let data = parse(include_str!("../example.txt"));
+    let field = distances_to(&data, (2, 5));
+    assert_eq!(field.distance((2, 5)), Some(0));
+    assert_eq!(field.distance((0, 0)), Some(31));
+    assert_eq!(field.distance((4, 0)), Some(29));
+    assert_eq!(field.distance((2, 4)), Some(1));
+    assert_eq!(field.distance((3, 5)), Some(7));
+}
+
+fn shortest_path(
+    data: &Grid,
+    start: (usize, usize),
+    end: (usize, usize),
+    rules: &MovementRules,
+) -> Option<(u64, Vec<(usize, usize)>)> {
+    let target = (end, data.get(end)?);
+    let index = |(x, y): (usize, usize)| x * data.width + y;
+    let mut costs = vec![None; data.squares.len()];
+    let mut previous = vec![None; data.squares.len()];
+    let mut queue = BinaryHeap::new();
+
+    costs[index(start)] = Some(0);
+    queue.push(Reverse((
+        rules.estimate((start, data.get(start)?), target),
+        start,
+    )));
+
+    while let Some(Reverse((_, entry_pos))) = queue.pop() {
+        let cost = costs[index(entry_pos)].unwrap();
+        if entry_pos == end {
+            let mut path = vec![end];
+            while let Some(step) = previous[index(*path.last().unwrap())] {
+                path.push(step);
+            }
+            path.reverse();
+            return Some((cost, path));
+        }
+
+        let square = &data[entry_pos];
+        for next_pos in data.neighbours(entry_pos, rules.diagonal) {
+            let next_square = &data[next_pos];
+            if !rules.can_move(square, next_square) {
+                continue;
+            }
+
+            let next_cost = cost + rules.cost(square, next_square);
+            if costs[index(next_pos)]
+                .map(|c| next_cost < c)
+                .unwrap_or(true)
+            {
+                costs[index(next_pos)] = Some(next_cost);
+                previous[index(next_pos)] = Some(entry_pos);
+                let estimate = rules.estimate((next_pos, next_square), target);
+                queue.push(Reverse((next_cost + estimate, next_pos)));
+            }
+        }
+    }
+
+    None
+}
+
+#[test]
+fn can_search_with_rules() {
+    let data = parse(include_str!("../example.txt"));
+    let rules = MovementRules {
+        diagonal: true,
+        ..MovementRules::default()
+    };
+    let (cost, path) = shortest_path(&data, (0, 0), (2, 5), &rules).unwrap();
+    assert_eq!(cost as usize, path.len() - 1);
+    assert!(cost < 31);
+    assert_eq!(
+        distances_to_with(&data, (2, 5), &rules).distance((0, 0)),
+        Some(cost)
+    );
+
+    // charging for climbing makes every route cost at least the total ascent
+    let rules = MovementRules {
+        climb_cost: 2,
+        descent_cost: 1,
+        ..MovementRules::default()
+    };
+    let (cost, path) = shortest_path(&data, (0, 0), (2, 5), &rules).unwrap();
+    let expected: u64 = path
+        .windows(2)
+        .map(|step| rules.cost(&data[step[0]], &data[step[1]]))
+        .sum();
+    assert_eq!(cost, expected);
+    assert!(cost >= 31 + 25 * 2);
+    let field = distances_to_with(&data, (2, 5), &rules);
+    assert_eq!(field.distance((0, 0)), Some(cost));
+    assert_eq!(field.path((0, 0)).map(|path| path.len()), Some(path.len()));
+}
+
+#[test]
+fn can_find_shortest_path() {
+    let data = parse(include_str!("../example.txt"));
+    let path = shortest_path(&data, (0, 0), (2, 5), &MovementRules::default())
         .unwrap()
-        .0;
-    let end = *data
-        .iter()
-        .find(|(_, value)| **value == Square::End)
+        .1;
+    assert_eq!(path.len(), 32);
+    assert_eq!(path.first(), Some(&(0, 0)));
+    assert_eq!(path.last(), Some(&(2, 5)));
+    for step in path.windows(2) {
+        assert!(data.neighbours(step[0], false).any(|n| n == step[1]));
+        assert!(MovementRules::default().can_move(&data[step[0]], &data[step[1]]));
+    }
+    let data = parse("Sc\nbE\n");
+    assert_eq!(
+        shortest_path(&data, (0, 0), (1, 1), &MovementRules::default()),
+        None
+    );
+}
+
+fn arrow(from: (usize, usize), to: (usize, usize)) -> char {
+    match (
+        to.0 as isize - from.0 as isize,
+        to.1 as isize - from.1 as isize,
+    ) {
+        (1, 0) => 'v',
+        (-1, 0) => '^',
+        (0, 1) => '>',
+        (0, -1) => '<',
+        (1, 1) | (-1, -1) => '\\',
+        (1, -1) | (-1, 1) => '/',
+        _ => '?',
+    }
+}
+
+fn path_markers(path: &[(usize, usize)]) -> BTreeMap<(usize, usize), char> {
+    let mut markers: BTreeMap<(usize, usize), char> = path
+        .windows(2)
+        .map(|step| (step[0], arrow(step[0], step[1])))
+        .collect();
+    if let Some(last) = path.last() {
+        markers.insert(*last, 'E');
+    }
+    markers
+}
+
+fn render_path(data: &Grid, path: &[(usize, usize)]) -> String {
+    let markers = path_markers(path);
+    let mut output = String::new();
+    for x in 0..data.height {
+        for y in 0..data.width {
+            output.push(markers.get(&(x, y)).copied().unwrap_or('.'));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[test]
+fn can_render_path() {
+    let data = parse(include_str!("../example.txt"));
+    let path = shortest_path(&data, (0, 0), (2, 5), &MovementRules::default())
         .unwrap()
-        .0;
+        .1;
+    let rendered = render_path(&data, &path);
+    assert_eq!(rendered.lines().count(), 5);
+    assert_eq!(rendered.chars().filter(|c| "<>^v".contains(*c)).count(), 31);
+    assert_eq!(rendered.lines().nth(2).unwrap().chars().nth(5), Some('E'));
+}
 
-    shortest_distance(data, start, end)
+fn render_elevation(data: &Grid, path: &[(usize, usize)]) -> String {
+    let markers = path_markers(path);
+    let mut output = String::new();
+    for x in 0..data.height {
+        for y in 0..data.width {
+            let square = data[(x, y)];
+            // 24-step greyscale ramp of the 256-colour palette
+            let background = 232 + square.elevation() as usize * 23 / 25;
+            let foreground = if background < 244 { 255 } else { 232 };
+            let symbol = match markers.get(&(x, y)) {
+                Some(marker) => marker.to_string(),
+                None => square.to_string(),
+            };
+            if markers.contains_key(&(x, y)) {
+                output.push_str(&format!("\x1b[48;5;{background};1;38;5;196m{symbol}"));
+            } else {
+                output.push_str(&format!(
+                    "\x1b[48;5;{background};38;5;{foreground}m{symbol}"
+                ));
+            }
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
 }
 
-fn solve_two(data: &BTreeMap<(usize, usize), Square>) -> u64 {
-    let end = *data
-        .iter()
-        .find(|(_, value)| **value == Square::End)
+#[test]
+fn can_render_elevation() {
+    let data = parse(include_str!("../example.txt"));
+    let path = shortest_path(&data, (0, 0), (2, 5), &MovementRules::default())
         .unwrap()
-        .0;
+        .1;
+    let rendered = render_elevation(&data, &path);
+    assert_eq!(rendered.lines().count(), 5);
+    assert!(rendered.starts_with("\x1b[48;5;232;1;38;5;196m"));
+    assert!(rendered.lines().all(|line| line.ends_with("\x1b[0m")));
+}
+
+fn find(data: &Grid, square: Square) -> (usize, usize) {
+    data.iter().find(|(_, value)| **value == square).unwrap().0
+}
+
+// both parts read the same distances to E
+fn solve(data: &Grid, field: &DistanceField) -> u64 {
+    field.distance(find(data, Square::Start)).unwrap()
+}
 
+// none if no `a` square can reach E, generated maps may have none at all
+fn solve_two(data: &Grid, field: &DistanceField) -> Option<u64> {
     data.iter()
         .filter(|(_, value)| **value == Square::Height(0))
-        .map(|(start, _)| shortest_distance(&data, *start, end))
+        .filter_map(|(start, _)| field.distance(start))
         .min()
-        .unwrap()
 }
 
 #[test]
 fn can_solve() {
     let data = parse(include_str!("../example.txt"));
-    assert_eq!(solve(&data), 31);
+    let field = distances_to(&data, find(&data, Square::End));
+    assert_eq!(solve(&data, &field), 31);
+}
+
+#[test]
+fn can_solve_two() {
+    let data = parse(include_str!("../example.txt"));
+    let field = distances_to(&data, find(&data, Square::End));
+    assert_eq!(solve_two(&data, &field), Some(29));
+}
+
+fn parse_rules(args: &[String]) -> Result<MovementRules, String> {
+    fn value<T: FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+        let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid value {value} for {flag}"))
+    }
+    let mut rules = MovementRules::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--diagonal" => rules.diagonal = true,
+            "--max-climb" => rules.max_climb = value(flag, args.next())?,
+            "--max-descent" => rules.max_descent = Some(value(flag, args.next())?),
+            "--step-cost" => rules.step_cost = value(flag, args.next())?,
+            "--climb-cost" => rules.climb_cost = value(flag, args.next())?,
+            "--descent-cost" => rules.descent_cost = value(flag, args.next())?,
+            other => return Err(format!("unknown option {other}")),
+        }
+    }
+    Ok(rules)
+}
+
+#[test]
+fn can_parse_rules() {
+    let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<_>>();
+    let rules = parse_rules(&args("--diagonal --max-climb 255 --max-descent 3")).unwrap();
+    assert!(rules.diagonal);
+    assert_eq!((rules.max_climb, rules.max_descent), (255, Some(3)));
+    assert_eq!(
+        parse_rules(&args("--max-climb 300")).map(|_| ()),
+        Err("invalid value 300 for --max-climb".into())
+    );
+    assert!(parse_rules(&args("--max-descent 256")).is_err());
+    assert!(parse_rules(&args("--step-cost")).is_err());
+    assert!(parse_rules(&args("--sideways")).is_err());
 }
 
 fn main() {
-    let file = std::env::args().nth(1).unwrap();
-    let data = std::fs::read_to_string(file).unwrap();
+    let args: Vec<String> = std::env::args().collect();
+    if args[1] == "generate" {
+        let number = |index: usize| args.get(index).map(|arg| arg.parse().unwrap());
+        let terrain = generate::Terrain {
+            width: number(2).unwrap() as usize,
+            height: number(3).unwrap() as usize,
+            length: number(4).map(|length| length as usize),
+            starts: number(5).unwrap_or(0) as usize,
+            seed: number(6).unwrap_or(1),
+        };
+        match generate::generate(&terrain) {
+            Ok(grid) => print!("{grid}"),
+            Err(error) => eprintln!("{error}"),
+        }
+        return;
+    }
+    if args[1] == "import" {
+        let image = std::fs::read(&args[2]).unwrap();
+        let position = |index: usize| {
+            args.get(index).map(|arg: &String| {
+                let (x, y) = arg.split_once(',').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+        };
+        match pnm::read_pnm(&image, position(3), position(4)) {
+            Ok(grid) => print!("{grid}"),
+            Err(error) => eprintln!("{error}"),
+        }
+        return;
+    }
+    let data = std::fs::read_to_string(&args[1]).unwrap();
     let data = parse(&data);
-    let result = solve(&data);
-    println!("{result}");
-    let result = solve_two(&data);
+    let start = find(&data, Square::Start);
+    let end = find(&data, Square::End);
+    let field = distances_to(&data, end);
+    let result = solve(&data, &field);
     println!("{result}");
+    match solve_two(&data, &field) {
+        Some(result) => println!("{result}"),
+        None => eprintln!("no square of elevation a can reach E"),
+    }
+    if args.get(2).map(|mode| mode.as_str()) == Some("route") {
+        let waypoints: Vec<(usize, usize)> = args[3..]
+            .iter()
+            .map(|arg| {
+                let (x, y) = arg.split_once(',').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect();
+        let rules = MovementRules::default();
+        match waypoints::plan(&data, &rules, start, &waypoints, end) {
+            Ok(route) => {
+                println!("{}", route.length);
+                print!("{}", render_path(&data, &route.path));
+            }
+            Err(error) => eprintln!("{error}"),
+        }
+        return;
+    }
+    if args.get(2).map(|mode| mode.as_str()) == Some("pgm") {
+        let file = std::fs::File::create(&args[3]).unwrap();
+        pnm::write_pgm(&data, std::io::BufWriter::new(file)).unwrap();
+        return;
+    }
+    if args.get(2).map(|mode| mode.as_str()) == Some("distances") {
+        let file = std::fs::File::create(&args[3]).unwrap();
+        let rules = match parse_rules(&args[4..]) {
+            Ok(rules) => rules,
+            Err(error) => return eprintln!("{error}"),
+        };
+        let field = distances_to_with(&data, end, &rules);
+        pnm::write_distances(&field, std::io::BufWriter::new(file)).unwrap();
+        return;
+    }
+    let render = match args.get(2).map(|mode| mode.as_str()) {
+        Some("path") => render_path,
+        Some("color") => render_elevation,
+        _ => return,
+    };
+    let rules = match parse_rules(&args[3..]) {
+        Ok(rules) => rules,
+        Err(error) => return eprintln!("{error}"),
+    };
+    let (cost, path) = shortest_path(&data, start, end, &rules).expect("no route to E");
+    println!("{cost}");
+    print!("{}", render(&data, &path));
+    let field = distances_to_with(&data, end, &rules);
+    let best = data
+        .iter()
+        .filter(|(_, value)| **value == Square::Height(0))
+        .filter_map(|(start, _)| field.distance(start).map(|d| (d, start)))
+        .min();
+    if let Some((cost, start)) = best {
+        println!("{cost}");
+        print!("{}", render(&data, &field.path(start).unwrap()));
+    }
 }
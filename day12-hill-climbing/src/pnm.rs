@@ -0,0 +1,266 @@
+use super::{DistanceField, Grid, Square};
+use std::io::{self, Write};
+
+const LEVELS: u32 = 26;
+
+// colour stops for the distance field, from close (dark blue) to far (yellow)
+const RAMP: [[u8; 3]; 5] = [
+    [68, 1, 84],
+    [59, 82, 139],
+    [33, 145, 140],
+    [94, 201, 98],
+    [253, 231, 37],
+];
+
+struct Header<'a> {
+    magic: &'a [u8],
+    width: usize,
+    height: usize,
+    maxval: u32,
+    raster: &'a [u8],
+}
+
+fn header(data: &[u8]) -> Result<Header<'_>, String> {
+    let mut fields = vec![];
+    let mut position = 0;
+    while fields.len() < 4 {
+        match data.get(position) {
+            None => return Err("truncated header".into()),
+            Some(b'#') => {
+                while data.get(position).map(|c| *c != b'\n').unwrap_or(false) {
+                    position += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => position += 1,
+            Some(_) => {
+                let begin = position;
+                while data
+                    .get(position)
+                    .map(|c| !c.is_ascii_whitespace())
+                    .unwrap_or(false)
+                {
+                    position += 1;
+                }
+                fields.push(&data[begin..position]);
+            }
+        }
+    }
+    let number = |field: &[u8]| -> Result<u32, String> {
+        std::str::from_utf8(field)
+            .ok()
+            .and_then(|field| field.parse().ok())
+            .ok_or_else(|| format!("invalid header field {}", String::from_utf8_lossy(field)))
+    };
+    let header = Header {
+        magic: fields[0],
+        width: number(fields[1])? as usize,
+        height: number(fields[2])? as usize,
+        maxval: number(fields[3])?,
+        // a single whitespace character separates the header from the raster
+        raster: data.get(position + 1..).unwrap_or(&[]),
+    };
+    if header.maxval == 0 || header.maxval > 65535 {
+        return Err(format!("invalid maximum value {}", header.maxval));
+    }
+    Ok(header)
+}
+
+// one sample per pixel, colour pixels are reduced to their luminance
+fn samples(header: &Header) -> Result<Vec<u32>, String> {
+    let channels = match header.magic {
+        b"P2" | b"P5" => 1,
+        b"P3" | b"P6" => 3,
+        other => {
+            return Err(format!(
+                "unsupported format {}",
+                String::from_utf8_lossy(other)
+            ))
+        }
+    };
+    let count = header.width * header.height * channels;
+    let samples: Vec<u32> = match header.magic {
+        b"P2" | b"P3" => std::str::from_utf8(header.raster)
+            .map_err(|e| e.to_string())?
+            .split_ascii_whitespace()
+            .take(count)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| format!("invalid sample {value}: {e}"))
+            })
+            .collect::<Result<_, _>>()?,
+        _ if header.maxval < 256 => header
+            .raster
+            .iter()
+            .take(count)
+            .map(|value| *value as u32)
+            .collect(),
+        _ => header
+            .raster
+            .chunks_exact(2)
+            .take(count)
+            .map(|value| u16::from_be_bytes([value[0], value[1]]) as u32)
+            .collect(),
+    };
+    if samples.len() != count {
+        return Err(format!("expected {count} samples, got {}", samples.len()));
+    }
+    if let Some(sample) = samples.iter().find(|s| **s > header.maxval) {
+        return Err(format!("sample {sample} exceeds maximum {}", header.maxval));
+    }
+    Ok(match channels {
+        1 => samples,
+        // rec. 601 weights, the same as most greyscale conversions
+        _ => samples
+            .chunks_exact(3)
+            .map(|rgb| (rgb[0] * 299 + rgb[1] * 587 + rgb[2] * 114 + 500) / 1000)
+            .collect(),
+    })
+}
+
+// Reads a greyscale image as a heightmap, colour images by their luminance.
+// Positions are (row, column) like everywhere else; without an explicit
+// start or end, the first lowest and the last highest pixel are used.
+pub fn read_pnm(
+    data: &[u8],
+    start: Option<(usize, usize)>,
+    end: Option<(usize, usize)>,
+) -> Result<Grid, String> {
+    let header = header(data)?;
+    let samples = samples(&header)?;
+    let mut grid = Grid {
+        width: header.width,
+        height: header.height,
+        squares: samples
+            .iter()
+            .map(|sample| Square::Height((sample * LEVELS / (header.maxval + 1)) as u8))
+            .collect(),
+    };
+    let lowest = grid
+        .iter()
+        .min_by_key(|(_, s)| s.elevation())
+        .map(|(p, _)| p);
+    let highest = grid
+        .iter()
+        .max_by_key(|(_, s)| s.elevation())
+        .map(|(p, _)| p);
+    let (start, end) = (start.or(lowest), end.or(highest));
+    if let Some(both) = start.filter(|start| Some(*start) == end) {
+        return Err(format!("start and end are both at {both:?}"));
+    }
+    for (position, square) in [(start, Square::Start), (end, Square::End)] {
+        let position = position.ok_or("empty image")?;
+        if !grid.contains(position) {
+            return Err(format!("{position:?} is outside of the image"));
+        }
+        grid.squares[position.0 * grid.width + position.1] = square;
+    }
+    Ok(grid)
+}
+
+pub fn write_pgm<W: Write>(grid: &Grid, mut writer: W) -> io::Result<()> {
+    write!(writer, "P5\n{} {}\n255\n", grid.width, grid.height)?;
+    let raster: Vec<u8> = grid
+        .squares
+        .iter()
+        .map(|square| (square.elevation() as u32 * 255 / (LEVELS - 1)) as u8)
+        .collect();
+    writer.write_all(&raster)
+}
+
+fn colour(fraction: f64) -> [u8; 3] {
+    let scaled = fraction.clamp(0.0, 1.0) * (RAMP.len() - 1) as f64;
+    let index = (scaled as usize).min(RAMP.len() - 2);
+    let t = scaled - index as f64;
+    let (a, b) = (RAMP[index], RAMP[index + 1]);
+    [0, 1, 2].map(|c| (a[c] as f64 + (b[c] as f64 - a[c] as f64) * t).round() as u8)
+}
+
+// Writes the distance field as a colour image, unreachable squares are black.
+pub fn write_distances<W: Write>(field: &DistanceField, mut writer: W) -> io::Result<()> {
+    write!(writer, "P6\n{} {}\n255\n", field.width, field.height)?;
+    let max = field
+        .distances
+        .iter()
+        .flatten()
+        .max()
+        .copied()
+        .unwrap_or(0)
+        .max(1);
+    let raster: Vec<u8> = field
+        .distances
+        .iter()
+        .flat_map(|distance| match distance {
+            Some(distance) => colour(*distance as f64 / max as f64),
+            None => [0, 0, 0],
+        })
+        .collect();
+    writer.write_all(&raster)
+}
+
+#[test]
+fn can_read_ascii_pgm() {
+    let data = b"P2\n# a comment\n3 2\n255\n0 10 100\n128 200 255\n";
+    let grid = read_pnm(data, None, None).unwrap();
+    assert_eq!((grid.width, grid.height), (3, 2));
+    assert_eq!(grid[(0, 0)], Square::Start);
+    assert_eq!(grid[(0, 1)], Square::Height(1));
+    assert_eq!(grid[(0, 2)], Square::Height(10));
+    assert_eq!(grid[(1, 0)], Square::Height(13));
+    assert_eq!(grid[(1, 1)], Square::Height(20));
+    assert_eq!(grid[(1, 2)], Square::End);
+    let grid = read_pnm(data, Some((0, 2)), Some((1, 0))).unwrap();
+    assert_eq!(grid.to_string(), "abS\nEuz\n");
+}
+
+#[test]
+fn can_round_trip_binary_pgm() {
+    let grid = super::parse(include_str!("../example.txt"));
+    let mut output = vec![];
+    write_pgm(&grid, &mut output).unwrap();
+    assert!(output.starts_with(b"P5\n8 5\n255\n"));
+    let read = read_pnm(&output, Some((0, 0)), Some((2, 5))).unwrap();
+    assert_eq!(read, grid);
+
+    let wide = b"P5 2 1 65535 \x00\x00\xff\xff";
+    let grid = read_pnm(wide, None, None).unwrap();
+    assert_eq!(grid.to_string(), "SE\n");
+}
+
+#[test]
+fn can_reject_invalid_images() {
+    assert!(read_pnm(b"P4\n1 1\n255\n0\n", None, None).is_err());
+    assert!(read_pnm(b"P3\n2 1\n255\n0 0 0 0 0\n", None, None).is_err());
+    assert!(read_pnm(b"P2\n2 2\n255\n0 0 0\n", None, None).is_err());
+    assert!(read_pnm(b"P2\n1 1\n15\n16\n", None, None).is_err());
+    assert!(read_pnm(b"P2\n1 1\n", None, None).is_err());
+    assert!(read_pnm(b"P2\n2 1\n255\n0 0\n", Some((0, 3)), None).is_err());
+    // a single pixel is both the lowest and the highest
+    assert!(read_pnm(b"P2\n1 1\n255\n7\n", None, None).is_err());
+    assert!(read_pnm(b"P2\n2 1\n255\n0 9\n", Some((0, 1)), None).is_err());
+}
+
+#[test]
+fn can_read_colour_images() {
+    // pure red, green and blue, then white
+    let ascii = b"P3\n4 1\n255\n255 0 0  0 255 0  0 0 255  255 255 255\n";
+    let grid = read_pnm(ascii, None, None).unwrap();
+    assert_eq!(grid.to_string(), "hpSE\n");
+    let binary = [
+        b"P6\n4 1\n255\n".as_slice(),
+        &[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255],
+    ]
+    .concat();
+    assert_eq!(read_pnm(&binary, None, None), Ok(grid));
+}
+
+#[test]
+fn can_write_distances() {
+    let grid = super::parse("xyEa\n");
+    let field = super::distances_to(&grid, (0, 2));
+    let mut output = vec![];
+    write_distances(&field, &mut output).unwrap();
+    let (header, raster) = output.split_at(11);
+    assert_eq!(header, b"P6\n4 1\n255\n");
+    assert_eq!(raster, [RAMP[4], RAMP[2], RAMP[0], [0; 3]].concat());
+}
@@ -0,0 +1,172 @@
+use super::{distances_to_with, Grid, MovementRules};
+
+const MAX_WAYPOINTS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub length: u64,
+    pub order: Vec<(usize, usize)>,
+    pub path: Vec<(usize, usize)>,
+}
+
+pub fn plan(
+    grid: &Grid,
+    rules: &MovementRules,
+    start: (usize, usize),
+    waypoints: &[(usize, usize)],
+    end: (usize, usize),
+) -> Result<Route, String> {
+    if waypoints.len() > MAX_WAYPOINTS {
+        return Err(format!(
+            "at most {MAX_WAYPOINTS} waypoints are supported, got {}",
+            waypoints.len()
+        ));
+    }
+    let points = [("start", start), ("end", end)]
+        .into_iter()
+        .chain(waypoints.iter().map(|w| ("waypoint", *w)));
+    for (name, point) in points {
+        if grid.get(point).is_none() {
+            return Err(format!("{name} {point:?} is outside of the map"));
+        }
+    }
+
+    // one reverse search per target gives the distance to it from anywhere,
+    // waypoints are indexed 0..n and the end is n.
+    let targets: Vec<(usize, usize)> = waypoints.iter().copied().chain([end]).collect();
+    let fields: Vec<_> = targets
+        .iter()
+        .map(|target| distances_to_with(grid, *target, rules))
+        .collect();
+    let count = waypoints.len();
+    let full = (1 << count) - 1;
+
+    // best[mask][last] is the cheapest way from the start through the
+    // waypoints in mask, ending at waypoint last.
+    let mut best = vec![vec![None; count]; 1 << count];
+    for (index, field) in fields[..count].iter().enumerate() {
+        best[1 << index][index] = field.distance(start).map(|d| (d, index));
+    }
+    for mask in 1..=full {
+        for last in 0..count {
+            let Some((cost, _)) = best[mask][last] else {
+                continue;
+            };
+            for (next, field) in fields[..count].iter().enumerate() {
+                if mask & (1 << next) != 0 {
+                    continue;
+                }
+                let Some(step) = field.distance(waypoints[last]) else {
+                    continue;
+                };
+                let entry = &mut best[mask | (1 << next)][next];
+                if entry.map(|(c, _)| cost + step < c).unwrap_or(true) {
+                    *entry = Some((cost + step, last));
+                }
+            }
+        }
+    }
+
+    let (length, mut last) = if count == 0 {
+        let length = fields[0].distance(start).ok_or("end is unreachable")?;
+        (length, None)
+    } else {
+        (0..count)
+            .filter_map(|last| {
+                let (cost, _) = best[full][last]?;
+                Some((cost + fields[count].distance(waypoints[last])?, Some(last)))
+            })
+            .min()
+            .ok_or("no route visits every waypoint")?
+    };
+
+    let mut stops = vec![count];
+    let mut mask = full;
+    while let Some(index) = last {
+        stops.push(index);
+        let (_, previous) = best[mask][index].unwrap();
+        mask &= !(1 << index);
+        last = (mask != 0).then_some(previous);
+    }
+    stops.reverse();
+
+    let mut path = vec![start];
+    for stop in &stops {
+        let from = *path.last().unwrap();
+        path.extend(fields[*stop].path(from).unwrap().into_iter().skip(1));
+    }
+    let order = stops[..count].iter().map(|stop| targets[*stop]).collect();
+
+    Ok(Route {
+        length,
+        order,
+        path,
+    })
+}
+
+#[test]
+fn can_plan_without_waypoints() {
+    let grid = super::parse(include_str!("../example.txt"));
+    let route = plan(&grid, &MovementRules::default(), (0, 0), &[], (2, 5)).unwrap();
+    assert_eq!(route.length, 31);
+    assert_eq!(route.order, vec![]);
+    assert_eq!(route.path.len(), 32);
+}
+
+#[test]
+fn can_plan_with_waypoints() {
+    let grid = super::parse(include_str!("../example.txt"));
+    let rules = MovementRules::default();
+    let waypoints = [(4, 7), (0, 7), (4, 0)];
+    let route = plan(&grid, &rules, (0, 0), &waypoints, (2, 5)).unwrap();
+    let mut order = route.order.clone();
+    order.sort();
+    assert_eq!(order, vec![(0, 7), (4, 0), (4, 7)]);
+    assert_eq!(route.path.len() as u64, route.length + 1);
+    assert_eq!(route.path.first(), Some(&(0, 0)));
+    assert_eq!(route.path.last(), Some(&(2, 5)));
+    for waypoint in waypoints {
+        assert!(route.path.contains(&waypoint));
+    }
+    for step in route.path.windows(2) {
+        assert!(grid.neighbours(step[0], false).any(|n| n == step[1]));
+        assert!(rules.can_move(&grid[step[0]], &grid[step[1]]));
+    }
+
+    // brute force over every order
+    let distance = |from, to| super::shortest_path(&grid, from, to, &rules).unwrap().0;
+    let orders = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let brute = orders
+        .iter()
+        .map(|order| {
+            let stops: Vec<_> = std::iter::once((0, 0))
+                .chain(order.iter().map(|i| waypoints[*i]))
+                .chain([(2, 5)])
+                .collect();
+            stops.windows(2).map(|s| distance(s[0], s[1])).sum::<u64>()
+        })
+        .min()
+        .unwrap();
+    assert_eq!(route.length, brute);
+}
+
+#[test]
+fn can_report_unreachable_waypoints() {
+    let grid = super::parse("Sc\nbE\n");
+    let rules = MovementRules::default();
+    assert!(plan(&grid, &rules, (0, 0), &[(1, 0)], (1, 1)).is_err());
+    assert!(plan(&grid, &rules, (0, 0), &[(5, 5)], (1, 1)).is_err());
+    assert!(plan(&grid, &rules, (0, 0), &[(0, 0); 17], (1, 1)).is_err());
+    assert_eq!(
+        plan(&grid, &rules, (0, 0), &[], (2, 1)),
+        Err("end (2, 1) is outside of the map".to_string())
+    );
+    assert!(plan(&grid, &rules, (0, 9), &[], (1, 1)).is_err());
+}